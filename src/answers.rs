@@ -0,0 +1,87 @@
+use std::{collections::BTreeMap, error::Error, fs, io, path::Path, path::PathBuf};
+
+/// Where the checked-in puzzle inputs and their answers live.
+pub const INPUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/input/2023");
+
+pub fn input_path(day: u32) -> PathBuf {
+    Path::new(INPUT_DIR).join(format!("day{day}.txt"))
+}
+
+pub fn manifest_path() -> PathBuf {
+    Path::new(INPUT_DIR).join("answers.json")
+}
+
+/// Known-good answers for the real puzzle inputs, keyed by day and part.
+/// Stored as JSON such as `{ "1": { "1": "55447" } }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    days: BTreeMap<u32, BTreeMap<u32, String>>,
+}
+
+impl Answers {
+    /// Reads a manifest. A missing file is treated as an empty manifest so
+    /// that answers can be accepted into a new one.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(format!("cannot read {}: {err}", path.display()).into()),
+        };
+
+        let days = serde_json::from_str(&json)
+            .map_err(|err| format!("cannot parse {}: {err}", path.display()))?;
+
+        Ok(Answers { days })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(&self.days)?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.days.get(&day)?.get(&part).map(String::as_str)
+    }
+
+    /// Records `answer`, returning the answer it replaced.
+    pub fn set(&mut self, day: u32, part: u32, answer: String) -> Option<String> {
+        self.days.entry(day).or_default().insert(part, answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("aoc-23-answers-{}.json", std::process::id()));
+
+        let mut answers = Answers::load(&path).unwrap();
+        assert_eq!(answers, Answers::default());
+
+        assert_eq!(answers.set(1, 2, "281".to_string()), None);
+        assert_eq!(
+            answers.set(1, 2, "282".to_string()),
+            Some("281".to_string())
+        );
+        answers.set(10, 1, "7".to_string());
+        answers.save(&path).unwrap();
+
+        let loaded = Answers::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, answers);
+        assert_eq!(loaded.get(1, 2), Some("282"));
+        assert_eq!(loaded.get(10, 1), Some("7"));
+        assert_eq!(loaded.get(1, 1), None);
+    }
+
+    #[test]
+    fn test_manifest_is_valid() {
+        let answers = Answers::load(&manifest_path()).unwrap();
+
+        assert!(answers.get(1, 1).is_some());
+    }
+}
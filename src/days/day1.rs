@@ -1,182 +1,234 @@
+use std::{convert::Infallible, error::Error, fmt};
+
+use crate::{matcher::PatternMatcher, parser::normalise, solution::Solution};
+
+/// What to do with a calibration line that has no recoverable value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnInvalidLine {
+    /// Leave the line out of the sum and record it in
+    /// [`Calibration::skipped`].
+    Skip,
+    /// Fail with a [`CalibrationError`] listing every invalid line.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineErrorKind {
+    Blank,
+    NoDigits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    /// 1-based line number in the calibration document.
+    pub line: usize,
+    pub kind: LineErrorKind,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            LineErrorKind::Blank => write!(f, "line {} is blank", self.line),
+            LineErrorKind::NoDigits => write!(f, "line {} contains no digits", self.line),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationError {
+    pub lines: Vec<LineError>,
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} invalid calibration line(s)", self.lines.len())?;
+        for line in &self.lines {
+            write!(f, "\n  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for CalibrationError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    pub sum: u32,
+    pub skipped: Vec<LineError>,
+}
+
+fn calibrate<F>(
+    input: &str,
+    on_invalid: OnInvalidLine,
+    line_value: F,
+) -> Result<Calibration, CalibrationError>
+where
+    F: Fn(&str) -> Option<u32>,
+{
+    let mut sum = 0;
+    let mut invalid = vec![];
+
+    for (i, line) in normalise(input).lines().enumerate() {
+        let kind = if line.trim().is_empty() {
+            LineErrorKind::Blank
+        } else if let Some(value) = line_value(line) {
+            sum += value;
+            continue;
+        } else {
+            LineErrorKind::NoDigits
+        };
+
+        invalid.push(LineError { line: i + 1, kind });
+    }
+
+    match on_invalid {
+        OnInvalidLine::Error if !invalid.is_empty() => Err(CalibrationError { lines: invalid }),
+        _ => Ok(Calibration {
+            sum,
+            skipped: invalid,
+        }),
+    }
+}
+
 #[aoc(day1, part1)]
-pub fn part1(input: &str) -> u32 {
-    input.lines().fold(0, |sum, line| {
-        sum + first_digit(line.chars()) * 10 + first_digit(line.chars().rev())
+pub fn part1(input: &str) -> Result<u32, CalibrationError> {
+    part1_with(input, OnInvalidLine::Error).map(|calibration| calibration.sum)
+}
+
+pub fn part1_with(input: &str, on_invalid: OnInvalidLine) -> Result<Calibration, CalibrationError> {
+    calibrate(input, on_invalid, |line| {
+        Some(first_digit(line.chars())? * 10 + first_digit(line.chars().rev())?)
     })
 }
 
-fn first_digit<I>(chars: I) -> u32
+fn first_digit<I>(mut chars: I) -> Option<u32>
 where
     I: Iterator<Item = char>,
 {
-    chars
-        .filter(|chr| chr.is_ascii_digit())
-        .map(|chr| chr.to_digit(10).unwrap())
-        .next()
-        .unwrap()
+    chars.find_map(|chr| chr.to_digit(10))
 }
 
 #[aoc(day1, part2)]
-pub fn part2(input: &str) -> u32 {
-    input.lines().fold(0, |sum, line| {
-        sum + first_digit_or_numword(|| line.chars(), false) * 10
-            + first_digit_or_numword(|| line.chars().rev(), true)
+pub fn part2(input: &str) -> Result<u32, CalibrationError> {
+    part2_with(input, OnInvalidLine::Error).map(|calibration| calibration.sum)
+}
+
+pub fn part2_with(input: &str, on_invalid: OnInvalidLine) -> Result<Calibration, CalibrationError> {
+    calibrate_with(input, &english_digit_matcher(), on_invalid)
+}
+
+/// Calibrates `input` by taking the first and last match of `matcher` on each
+/// line, so documents can spell their digits in any language or notation.
+pub fn calibrate_with(
+    input: &str,
+    matcher: &PatternMatcher<u32>,
+    on_invalid: OnInvalidLine,
+) -> Result<Calibration, CalibrationError> {
+    calibrate(input, on_invalid, |line| {
+        Some(matcher.find_first(line)?.value * 10 + matcher.find_last(line)?.value)
     })
 }
 
-fn first_digit_or_numword<I, F>(chars_builder: F, reverse: bool) -> u32
-where
-    I: Iterator<Item = char>,
-    F: Fn() -> I,
-{
-    for (i, chr) in chars_builder().enumerate() {
-        if chr.is_numeric() {
-            if let Some(num) = chr.to_digit(10) {
-                return num;
-            }
-        } else if reverse {
-            let mut maybe_num = None;
-
-            iter_digit_words_with_last_char(chr, |word| {
-                let res = parse_digit_word(word, chars_builder().skip(i), reverse);
-                if res {
-                    maybe_num = Some(word.number);
-                }
-                res
-            });
-
-            if let Some(num) = maybe_num {
-                return num;
-            }
-        } else {
-            let mut maybe_num = None;
-
-            iter_digit_words_with_first_char(chr, |word| {
-                let res = parse_digit_word(word, chars_builder().skip(i), reverse);
-                if res {
-                    maybe_num = Some(word.number);
-                }
-                res
-            });
-
-            if let Some(num) = maybe_num {
-                return num;
-            }
-        }
+const NUMBER_WORDS: [(&str, u32); 10] = [
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+/// Matches both the ASCII digits and the English words for them.
+pub fn english_digit_matcher() -> PatternMatcher<u32> {
+    PatternMatcher::new(
+        (0..10)
+            .map(|digit| (digit.to_string(), digit))
+            .chain(NUMBER_WORDS.map(|(word, digit)| (word.to_string(), digit))),
+    )
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+    const NAME: &'static str = "Trebuchet?!";
+
+    type Input = String;
+    type Output1 = Result<u32, CalibrationError>;
+    type Output2 = Result<u32, CalibrationError>;
+    type Error = Infallible;
+
+    fn parse(input: &str) -> Result<String, Infallible> {
+        Ok(normalise(input).into_owned())
     }
 
-    panic!("No digits found in line")
-}
-
-struct NumberWord {
-    pub word: &'static str,
-    pub number: u32,
-}
-
-const NUMBER_WORDS: [NumberWord; 10] = [
-    NumberWord {
-        word: "zero",
-        number: 0,
-    },
-    NumberWord {
-        word: "one",
-        number: 1,
-    },
-    NumberWord {
-        word: "two",
-        number: 2,
-    },
-    NumberWord {
-        word: "three",
-        number: 3,
-    },
-    NumberWord {
-        word: "four",
-        number: 4,
-    },
-    NumberWord {
-        word: "five",
-        number: 5,
-    },
-    NumberWord {
-        word: "six",
-        number: 6,
-    },
-    NumberWord {
-        word: "seven",
-        number: 7,
-    },
-    NumberWord {
-        word: "eight",
-        number: 8,
-    },
-    NumberWord {
-        word: "nine",
-        number: 9,
-    },
-];
+    fn part1(input: &String) -> Result<u32, CalibrationError> {
+        part1(input)
+    }
 
-fn parse_digit_word<I>(word: &NumberWord, mut actual_chars: I, reverse: bool) -> bool
-where
-    I: Iterator<Item = char>,
-{
-    if reverse {
-        word.word
-            .chars()
-            .rev()
-            .all(|chr| actual_chars.next() == Some(chr))
-    } else {
-        word.word
-            .chars()
-            .all(|chr| actual_chars.next() == Some(chr))
+    fn part2(input: &String) -> Result<u32, CalibrationError> {
+        part2(input)
     }
 }
 
-fn iter_digit_words_with_first_char<F>(first_chr: char, mut func: F) -> bool
-where
-    F: FnMut(&NumberWord) -> bool,
-{
-    match first_chr {
-        // zero
-        'z' => func(&NUMBER_WORDS[0]),
-        // one
-        'o' => func(&NUMBER_WORDS[1]),
-        // two, three
-        't' => func(&NUMBER_WORDS[2]) || func(&NUMBER_WORDS[3]),
-        // four, five
-        'f' => func(&NUMBER_WORDS[4]) || func(&NUMBER_WORDS[5]),
-        // six, seven
-        's' => func(&NUMBER_WORDS[6]) || func(&NUMBER_WORDS[7]),
-        // eight
-        'e' => func(&NUMBER_WORDS[8]),
-        // nine
-        'n' => func(&NUMBER_WORDS[9]),
-        _ => false,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::fixture_tests;
+
+    fixture_tests!(1, Day1);
+
+    #[test]
+    fn test_unnormalised_input() {
+        let input = "\u{feff}1abc2\r\ntwo1nine\rxtwone3four\r\n\r\n \n";
+
+        assert_eq!(part1(input), Ok(12 + 11 + 33));
+        assert_eq!(part2(input), Ok(12 + 29 + 24));
+        assert_eq!(
+            Day1::parse(input).unwrap(),
+            "1abc2\ntwo1nine\nxtwone3four\n"
+        );
     }
-}
 
-fn iter_digit_words_with_last_char<F>(last_chr: char, mut func: F) -> bool
-where
-    F: FnMut(&NumberWord) -> bool,
-{
-    match last_chr {
-        // zero, two
-        'o' => func(&NUMBER_WORDS[0]) || func(&NUMBER_WORDS[2]),
-        // one, three, five, nine
-        'e' => {
-            func(&NUMBER_WORDS[1])
-                || func(&NUMBER_WORDS[3])
-                || func(&NUMBER_WORDS[5])
-                || func(&NUMBER_WORDS[9])
-        }
-        // four
-        'r' => func(&NUMBER_WORDS[4]),
-        // six
-        'x' => func(&NUMBER_WORDS[6]),
-        // seven
-        'n' => func(&NUMBER_WORDS[7]),
-        // eight
-        't' => func(&NUMBER_WORDS[8]),
-        _ => false,
+    #[test]
+    fn test_invalid_lines() {
+        let input = "1abc2\nnothing\n\ntreb7uchet\n";
+
+        assert_eq!(
+            part1(input),
+            Err(CalibrationError {
+                lines: vec![
+                    LineError {
+                        line: 2,
+                        kind: LineErrorKind::NoDigits
+                    },
+                    LineError {
+                        line: 3,
+                        kind: LineErrorKind::Blank
+                    },
+                ]
+            })
+        );
+
+        let calibration = part1_with(input, OnInvalidLine::Skip).unwrap();
+        assert_eq!(calibration.sum, 12 + 77);
+        assert_eq!(calibration.skipped.len(), 2);
+
+        let calibration = part2_with(input, OnInvalidLine::Skip).unwrap();
+        assert_eq!(calibration.sum, 12 + 77);
+        assert_eq!(calibration.skipped[0].line, 2);
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let matcher = PatternMatcher::new([("un", 1), ("deux", 2), ("dix", 10), ("onze", 11)]);
+
+        let calibration = calibrate_with("deuxxundix\nonzeun\n", &matcher, OnInvalidLine::Error);
+
+        assert_eq!(calibration.map(|c| c.sum), Ok(2 * 10 + 10 + 11 * 10 + 1));
     }
 }
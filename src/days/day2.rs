@@ -1,59 +1,143 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
 use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::{char, line_ending, space1, u32, u8},
+    character::complete::{alpha1, char, space1},
+    combinator::{cut, peek, rest as remaining},
+    error::context,
     multi::separated_list0,
-    sequence::{delimited, tuple},
-    IResult, Parser,
+    sequence::{terminated, tuple},
+    Parser,
+};
+
+use crate::{
+    parser::{labelled, lines, normalise, number, parse_all, IResult, ParseError, SyntaxError},
+    solution::Solution,
 };
 
-pub type Round = (u32, u32, u32);
+/// A count of cubes for each named colour. Colours that are not present have
+/// a count of zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct CubeSet {
+    counts: BTreeMap<String, u32>,
+}
+
+impl CubeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, color: &str) -> u32 {
+        self.counts.get(color).copied().unwrap_or(0)
+    }
+
+    /// Adds `count` cubes of `color` to the set, returning the new count
+    /// of that colour. Returns `None`, leaving the set unchanged, if the
+    /// count would not fit in a `u32`.
+    pub fn checked_add(&mut self, color: &str, count: u32) -> Option<u32> {
+        let total = self.get(color).checked_add(count)?;
+        if total > 0 {
+            self.counts.insert(color.to_string(), total);
+        }
+        Some(total)
+    }
+
+    /// Adds `count` cubes of `color` to the set. Panics if the count of that
+    /// colour would not fit in a `u32`.
+    pub fn add(&mut self, color: &str, count: u32) {
+        self.checked_add(color, count)
+            .expect("cube count overflowed u32");
+    }
+
+    pub fn with(mut self, color: &str, count: u32) -> Self {
+        self.add(color, count);
+        self
+    }
+
+    /// The colours with a non-zero count, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.counts
+            .iter()
+            .map(|(color, &count)| (color.as_str(), count))
+    }
+
+    /// The element-wise maximum of the two sets.
+    pub fn union(&self, other: &CubeSet) -> CubeSet {
+        let mut union = self.clone();
+        for (color, count) in other.iter() {
+            let entry = union.counts.entry(color.to_string()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+        union
+    }
+
+    /// The product of the counts of every colour present in the set, or
+    /// `None` if it does not fit in a `u64`. Absent colours are left out
+    /// rather than counted as zero.
+    pub fn product(&self) -> Option<u64> {
+        self.counts
+            .values()
+            .try_fold(1u64, |product, &count| product.checked_mul(count as u64))
+    }
+}
+
+/// Cube sets are ordered element-wise, so `a <= b` holds when `b` has at
+/// least as many cubes as `a` of every colour.
+impl PartialOrd for CubeSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let self_le = self.iter().all(|(color, count)| count <= other.get(color));
+        let other_le = other.iter().all(|(color, count)| count <= self.get(color));
+
+        match (self_le, other_le) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
+        }
+    }
+}
+
+pub type Round = CubeSet;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Game {
     pub id: u32,
     pub rounds: Vec<Round>,
 }
 
-enum Color {
-    RED,
-    GREEN,
-    BLUE,
-}
-
-fn parse_round(input: &str) -> IResult<&str, Round> {
-    separated_list0(
+fn parse_round(input: &str) -> IResult<'_, Round> {
+    let (rest, turns) = separated_list0(
         char(','),
         tuple((
             space1,
-            u8,
-            space1,
-            alt((
-                tag("red").map(|_| Color::RED),
-                tag("green").map(|_| Color::GREEN),
-                tag("blue").map(|_| Color::BLUE),
-            )),
+            // Where the count starts, to point at it if the total overflows.
+            peek(remaining),
+            number,
+            cut(space1),
+            cut(context("a colour", alpha1)),
         ))
-        .map(|(_, count, _, color)| (count, color)),
+        .map(|(_, at, count, _, color)| (at, count, color)),
     )
-    .map(|turns| {
-        turns.into_iter().fold(
-            (0, 0, 0),
-            |mut round: Round, (count, color): (u8, Color)| {
-                *match color {
-                    Color::RED => &mut round.0,
-                    Color::GREEN => &mut round.1,
-                    Color::BLUE => &mut round.2,
-                } += count as u32;
-                round
-            },
-        )
-    })
-    .parse(input)
+    .parse(input)?;
+
+    let mut round = CubeSet::new();
+    for (at, count, color) in turns {
+        if round.checked_add(color, count).is_none() {
+            return Err(nom::Err::Failure(SyntaxError::new(
+                at,
+                "a total cube count that fits in a u32",
+            )));
+        }
+    }
+
+    Ok((rest, round))
 }
 
-fn parse_game(input: &str) -> IResult<&str, Game> {
+fn parse_game(input: &str) -> IResult<'_, Game> {
     tuple((
-        delimited(tag("Game "), u32, char(':')),
+        terminated(labelled("Game", number), char(':')),
         separated_list0(char(';'), parse_round),
     ))
     .map(|(id, rounds)| Game { id, rounds })
@@ -61,113 +145,321 @@ fn parse_game(input: &str) -> IResult<&str, Game> {
 }
 
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Game> {
-    let games = separated_list0(line_ending, parse_game).parse(input);
+pub fn input_generator(input: &str) -> Result<Vec<Game>, ParseError> {
+    let input: &str = &normalise(input);
+    parse_all(input, lines(parse_game))
+}
+
+/// The cubes loaded into the bag.
+pub type Bag = CubeSet;
+
+/// The colours named in the puzzle text.
+pub const PUZZLE_COLORS: [&str; 3] = ["red", "green", "blue"];
 
-    let (_, games) = games.unwrap();
-    return games;
+/// The bag given in the puzzle text.
+pub fn puzzle_bag() -> Bag {
+    CubeSet::new()
+        .with("red", 12)
+        .with("green", 13)
+        .with("blue", 14)
 }
 
-const MAX_RED: u32 = 12;
-const MAX_GREEN: u32 = 13;
-const MAX_BLUE: u32 = 14;
+/// A colour in a round that showed more cubes than the bag holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// 0-based index of the round within its game.
+    pub round: usize,
+    pub color: String,
+    pub count: u32,
+    pub limit: u32,
+}
 
-#[aoc(day2, part1)]
-pub fn part1(games: &Vec<Game>) -> u32 {
-    games
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub game_id: u32,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BagReport {
+    pub allowed: Vec<u32>,
+    pub rejected: Vec<Rejection>,
+}
+
+fn round_violations<'a>(
+    index: usize,
+    round: &'a Round,
+    bag: &'a Bag,
+) -> impl Iterator<Item = Violation> + 'a {
+    round
         .iter()
-        .filter(|game| {
-            !game
-                .rounds
-                .iter()
-                .any(|round| round.0 > MAX_RED || round.1 > MAX_GREEN || round.2 > MAX_BLUE)
+        .filter(|&(color, count)| count > bag.get(color))
+        .map(move |(color, count)| Violation {
+            round: index,
+            color: color.to_string(),
+            count,
+            limit: bag.get(color),
         })
-        .fold(0, |id_sum, game| id_sum + game.id)
+}
+
+/// Splits `games` into the IDs of those that are possible with `bag` and,
+/// for the rest, every round and colour that exceeded it.
+pub fn check_bag(games: &[Game], bag: &Bag) -> BagReport {
+    let mut report = BagReport::default();
+
+    for game in games {
+        let violations: Vec<Violation> = game
+            .rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(index, round)| round_violations(index, round, bag))
+            .collect();
+
+        if violations.is_empty() {
+            report.allowed.push(game.id);
+        } else {
+            report.rejected.push(Rejection {
+                game_id: game.id,
+                violations,
+            });
+        }
+    }
+
+    report
+}
+
+#[aoc(day2, part1)]
+pub fn part1(games: &[Game]) -> u64 {
+    check_bag(games, &puzzle_bag())
+        .allowed
+        .iter()
+        .map(|&id| id as u64)
+        .sum()
+}
+
+/// The power of a game's minimum cube set: the product of its red, green
+/// and blue counts. A game that never shows one of them has a power of zero,
+/// and other colours play no part.
+fn power(min_set: &CubeSet) -> u128 {
+    PUZZLE_COLORS
+        .iter()
+        .map(|color| min_set.get(color) as u128)
+        .product()
 }
 
 #[aoc(day2, part2)]
-pub fn part2(games: &Vec<Game>) -> u32 {
+pub fn part2(games: &[Game]) -> u128 {
     games
         .iter()
         .map(|game| {
-            game.rounds.iter().fold((0, 0, 0), |max, curr| {
-                (
-                    if max.0 < curr.0 { curr.0 } else { max.0 },
-                    if max.1 < curr.1 { curr.1 } else { max.1 },
-                    if max.2 < curr.2 { curr.2 } else { max.2 },
-                )
-            })
+            game.rounds
+                .iter()
+                .fold(CubeSet::new(), |min_set, round| min_set.union(round))
         })
-        .map(|min_set| min_set.0 * min_set.1 * min_set.2)
+        .map(|min_set| power(&min_set))
         .sum()
 }
 
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+    const NAME: &'static str = "Cube Conundrum";
+
+    type Input = Vec<Game>;
+    type Output1 = u64;
+    type Output2 = u128;
+    type Error = ParseError;
+
+    fn parse(input: &str) -> Result<Vec<Game>, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(games: &Vec<Game>) -> u64 {
+        part1(games)
+    }
+
+    fn part2(games: &Vec<Game>) -> u128 {
+        part2(games)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::{fixture, fixture_tests};
     use indoc::indoc;
 
+    fixture_tests!(2, Day2);
+
     #[test]
     fn test_parse() {
-        let games = input_generator(indoc! {
-            "
-            Game 1: 1 green, 2 blue; 13 red, 2 blue, 3 green; 4 green, 14 red
-            Game 2: 2 blue, 11 green; 4 blue, 12 red, 4 green; 7 red, 1 blue, 9 green; 10 green, 12 red, 6 blue
-            Game 3: 1 blue, 12 green, 2 red; 9 red, 16 green; 1 red, 10 green, 1 blue; 1 red, 14 green
-            Game 4: 8 green, 18 blue; 4 green, 14 blue, 2 red; 3 blue, 5 green, 11 red
-            Game 5: 7 red, 15 blue, 1 green; 13 blue; 18 red, 2 green, 9 blue; 19 blue, 5 green, 10 red; 9 green, 2 blue, 7 red
-            Game 6: 1 red, 8 blue, 2 green; 1 blue, 3 red, 5 green; 2 green, 3 red, 2 blue; 1 blue, 4 green
-            "
-        });
+        let games = input_generator(&fixture(2, "six-games")).unwrap();
 
         assert_eq!(games.len(), 6);
         assert_eq!(games[0].rounds.len(), 3);
-        assert_eq!(games[0].rounds[1].0, 13);
-        assert_eq!(games[0].rounds[1].1, 3);
-        assert_eq!(games[0].rounds[1].2, 2);
+        assert_eq!(games[0].rounds[1].get("red"), 13);
+        assert_eq!(games[0].rounds[1].get("green"), 3);
+        assert_eq!(games[0].rounds[1].get("blue"), 2);
     }
 
     #[test]
-    fn test_part1() {
-        let input = input_generator(indoc! {
-            "
-            Game 1: 1 green, 2 blue; 13 red, 2 blue, 3 green; 4 green, 14 red
-            Game 2: 2 blue, 11 green; 4 blue, 12 red, 4 green; 7 red, 1 blue, 9 green; 10 green, 12 red, 6 blue
-            Game 3: 1 blue, 12 green, 2 red; 9 red, 16 green; 1 red, 10 green, 1 blue; 1 red, 14 green
-            Game 4: 8 green, 18 blue; 4 green, 14 blue, 2 red; 3 blue, 5 green, 11 red
-            Game 5: 7 red, 15 blue, 1 green; 13 blue; 18 red, 2 green, 9 blue; 19 blue, 5 green, 10 red; 9 green, 2 blue, 7 red
-            Game 6: 1 red, 8 blue, 2 green; 1 blue, 3 red, 5 green; 2 green, 3 red, 2 blue; 1 blue, 4 green
-            "
-        });
+    fn test_check_bag() {
+        let input = input_generator(&fixture(2, "example")).unwrap();
+
+        let report = check_bag(&input, &puzzle_bag());
+
+        assert_eq!(report.allowed, vec![1, 2, 5]);
+        assert_eq!(report.rejected.len(), 2);
+        assert_eq!(report.rejected[0].game_id, 3);
+        assert_eq!(
+            report.rejected[0].violations,
+            vec![Violation {
+                round: 0,
+                color: "red".to_string(),
+                count: 20,
+                limit: 12
+            }]
+        );
+        assert_eq!(report.rejected[1].game_id, 4);
+        assert_eq!(report.rejected[1].violations.len(), 2);
+        assert_eq!(report.rejected[1].violations[0].color, "blue");
+        assert_eq!(report.rejected[1].violations[1].color, "red");
+
+        let report = check_bag(
+            &input,
+            &CubeSet::new()
+                .with("red", 20)
+                .with("green", 13)
+                .with("blue", 15),
+        );
 
-        assert_eq!(part1(&input), 2 + 6);
+        assert_eq!(report.allowed, vec![1, 2, 3, 4, 5]);
+        assert!(report.rejected.is_empty());
     }
 
     #[test]
-    fn test_part2_power_set() {
-        let input = input_generator(indoc! {
+    fn test_parse_error() {
+        let err = input_generator(indoc! {
             "
+            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+            Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
             Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+            Game 4: 1 green, 3 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
             "
-        });
+        })
+        .err()
+        .unwrap();
 
-        // (9, 16, 1)
+        assert_eq!(err.line, 4);
+        assert_eq!(err.column, 20);
+        assert!(err
+            .to_string()
+            .starts_with("expected a colour at line 4 col 20"));
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let input: String = (250..260)
+            .map(|id| format!("Game {id}: {} red, 2 blue; {} green\n", id + 50, id * 1000))
+            .collect();
+        let games = input_generator(&input).unwrap();
 
-        assert_eq!(part2(&input), 1560);
+        assert_eq!(games.len(), 10);
+        assert_eq!(games[9].id, 259);
+        assert_eq!(games[9].rounds[0].get("red"), 309);
+        assert_eq!(games[9].rounds[1].get("green"), 259_000);
+        assert_eq!(part1(&games), 0);
+        assert_eq!(
+            part2(&games),
+            (250..260u128)
+                .map(|id| (id + 50) * 2 * id * 1000)
+                .sum::<u128>()
+        );
+
+        let games = input_generator("Game 3000000000: 1 red\nGame 3000000001: 2 blue\n");
+        assert_eq!(part1(&games.unwrap()), 6_000_000_001);
+
+        let err = input_generator("Game 1: 4294967296 red\n").err().unwrap();
+        assert_eq!((err.line, err.column), (1, 9));
+        assert_eq!(err.expected, "a number that fits in a u32");
+
+        let err = input_generator("Game 1: 4294967295 red, 1 red\n")
+            .err()
+            .unwrap();
+        assert_eq!((err.line, err.column), (1, 25));
+        assert_eq!(err.expected, "a total cube count that fits in a u32");
+
+        let max = u32::MAX as u128;
+        let games = input_generator(&format!("Game 1: {max} red, {max} green, {max} blue\n"));
+        assert_eq!(part2(&games.unwrap()), max * max * max);
     }
 
     #[test]
-    fn test_part2_sum() {
+    fn test_arbitrary_colours() {
         let input = input_generator(indoc! {
             "
-            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-            Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
-            Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
-            Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
-            Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+            Game 1: 3 blue, 4 pink; 1 red, 2 pink
+            Game 2: 1 blue, 2 green; 3 teal
             "
-        });
+        })
+        .unwrap();
+
+        assert_eq!(input[0].rounds[0].get("pink"), 4);
+        assert_eq!(input[1].rounds[1].get("teal"), 3);
+
+        let report = check_bag(&input, &puzzle_bag().with("pink", 4));
+        assert_eq!(report.allowed, vec![1]);
+        assert_eq!(report.rejected[0].violations[0].color, "teal");
+        assert_eq!(report.rejected[0].violations[0].limit, 0);
+
+        // (blue 3, pink 4, red 1) has no green and (blue 1, green 2, teal 3)
+        // no red, so neither has any power.
+        assert_eq!(part2(&input), 0);
+        assert_eq!(
+            part2(&input_generator("Game 1: 3 blue\nGame 2: 1 red, 2 green, 3 blue\n").unwrap()),
+            6
+        );
+    }
+
+    #[test]
+    fn test_cube_set_ordering() {
+        let small = CubeSet::new().with("red", 1).with("blue", 2);
+        let large = CubeSet::new()
+            .with("red", 3)
+            .with("blue", 2)
+            .with("green", 1);
+        let other = CubeSet::new().with("red", 5);
+
+        assert!(small <= large);
+        assert!(small < large);
+        assert!(large > small);
+        assert_eq!(small.partial_cmp(&other), None);
+        assert_eq!(
+            small.union(&other),
+            CubeSet::new().with("red", 5).with("blue", 2)
+        );
+        assert_eq!(large.product(), Some(6));
+        assert_eq!(
+            large
+                .with("pink", u32::MAX)
+                .with("teal", u32::MAX)
+                .product(),
+            None
+        );
+        assert_eq!(CubeSet::new().with("red", 0), CubeSet::new());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let games = input_generator("Game 7: 3 blue, 4 red; 2 green\n").unwrap();
 
-        assert_eq!(part2(&input), 2286);
+        assert_eq!(
+            serde_json::to_value(&games).unwrap(),
+            serde_json::json!([
+                { "id": 7, "rounds": [{ "blue": 3, "red": 4 }, { "green": 2 }] }
+            ])
+        );
     }
 }
@@ -1,209 +1,342 @@
-use nom::{
-    branch::alt,
-    character::complete::{char, line_ending, none_of, one_of},
-    multi::{many1, separated_list0},
-    IResult, Parser,
+use crate::{
+    grid::{Grid, Point},
+    parser::{normalise, ParseError},
+    solution::Solution,
 };
 
-type Point = (i32, i32);
-
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Num {
     pub num: u32,
     pub length: i32,
     pub pos: Point,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Symbol {
     pub sym: char,
     pub pos: Point,
 }
 
+impl Num {
+    /// Every point this number's digits cover.
+    fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.length).map(|dx| self.pos.offset(dx, 0))
+    }
+
+    /// The ring of points around this number.
+    fn neighbours(&self) -> impl Iterator<Item = Point> + '_ {
+        let row = move |dy| (-1..=self.length).map(move |dx| self.pos.offset(dx, dy));
+
+        row(-1)
+            .chain([self.pos.offset(-1, 0), self.pos.offset(self.length, 0)])
+            .chain(row(1))
+    }
+}
+
+/// A symbol together with every number touching it.
+pub struct SymbolNeighbours<'a> {
+    pub symbol: &'a Symbol,
+    pub nums: Vec<&'a Num>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Product,
+    Sum,
+}
+
+impl SymbolNeighbours<'_> {
+    /// Combines the neighbouring numbers with `aggregate`.
+    pub fn ratio(&self, aggregate: Aggregate) -> u64 {
+        let values = self.nums.iter().map(|num| num.num as u64);
+        match aggregate {
+            Aggregate::Product => values.product(),
+            Aggregate::Sum => values.sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Occupant {
+    Symbol(usize),
+    Num(usize),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Board {
     pub symbols: Vec<Symbol>,
     pub nums: Vec<Num>,
+    /// What sits at each non-empty point, as an index into `symbols` or
+    /// `nums`. A number occupies one point per digit.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    occupied: Grid<Option<Occupant>>,
 }
 
-enum Node {
-    Symbol(char),
-    Number(u32, i32),
-}
+impl Board {
+    /// Finds the numbers and symbols on a schematic, where `.` is empty.
+    pub fn from_grid(grid: &Grid<char>) -> Result<Self, ParseError> {
+        let mut symbols: Vec<Symbol> = vec![];
+        let mut nums: Vec<Num> = vec![];
+        let mut occupied = Grid::new(grid.width(), grid.height(), None);
 
-fn parse_line(input: &str) -> IResult<&str, Vec<Option<Node>>> {
-    many1(alt((
-        char('.').map(|_| None),
-        many1(one_of("0123456789")).map(|c| {
-            Some(Node::Number(
-                str::parse(&c.iter().collect::<String>()).unwrap(),
-                c.len() as i32,
-            ))
-        }),
-        none_of("\n\r").map(|c| Some(Node::Symbol(c))),
-    )))
-    .parse(input)
-}
+        for (y, row) in grid.rows().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let pos = Point::new(x as i32, y as i32);
 
-#[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Board {
-    let (_, points) = separated_list0(line_ending, parse_line)
-        .parse(input)
-        .expect("Parsing failed");
-
-    let mut symbols: Vec<Symbol> = vec![];
-    let mut nums: Vec<Num> = vec![];
-
-    for (y, row) in points.into_iter().enumerate() {
-        let mut x = 0;
-        for cell in row.into_iter() {
-            x += match cell {
-                Some(cell) => match cell {
-                    Node::Symbol(c) => {
-                        symbols.push(Symbol {
-                            sym: c,
-                            pos: (x, y as i32),
-                        });
-                        1
+                if row[x].is_ascii_digit() {
+                    let digits: String = row[x..]
+                        .iter()
+                        .take_while(|chr| chr.is_ascii_digit())
+                        .collect();
+                    let num = digits.parse().map_err(|_| ParseError {
+                        line: y + 1,
+                        column: x + 1,
+                        snippet: row.iter().collect(),
+                        expected: "a number that fits in a u32".to_string(),
+                    })?;
+
+                    nums.push(Num {
+                        num,
+                        length: digits.len() as i32,
+                        pos,
+                    });
+                    for cell in nums[nums.len() - 1].cells() {
+                        occupied[cell] = Some(Occupant::Num(nums.len() - 1));
                     }
-                    Node::Number(num, length) => {
-                        nums.push(Num {
-                            num,
-                            length,
-                            pos: (x, y as i32),
-                        });
-                        length
+                    x += digits.len();
+                } else {
+                    if row[x] != '.' {
+                        symbols.push(Symbol { sym: row[x], pos });
+                        occupied[pos] = Some(Occupant::Symbol(symbols.len() - 1));
                     }
-                },
-                None => 1,
+                    x += 1;
+                }
             }
         }
+
+        Ok(Board {
+            symbols,
+            nums,
+            occupied,
+        })
+    }
+
+    fn occupant(&self, point: Point) -> Option<Occupant> {
+        self.occupied.get(point).copied().flatten()
+    }
+
+    /// The symbols touching `num`, including diagonally.
+    pub fn symbols_adjacent_to<'a>(&'a self, num: &'a Num) -> impl Iterator<Item = &'a Symbol> {
+        num.neighbours()
+            .filter_map(|point| match self.occupant(point) {
+                Some(Occupant::Symbol(i)) => Some(&self.symbols[i]),
+                _ => None,
+            })
     }
 
-    Board { symbols, nums }
+    /// The numbers touching `symbol`, including diagonally. Each number is
+    /// listed once even when several of its digits touch the symbol.
+    pub fn nums_adjacent_to(&self, symbol: &Symbol) -> Vec<&Num> {
+        let mut indices: Vec<usize> = symbol
+            .pos
+            .neighbours8()
+            .into_iter()
+            .filter_map(|point| match self.occupant(point) {
+                Some(Occupant::Num(i)) => Some(i),
+                _ => None,
+            })
+            .collect();
+
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(|i| &self.nums[i]).collect()
+    }
+
+    /// Every symbol with its neighbouring numbers, optionally restricted to
+    /// one symbol character and to symbols with exactly `neighbours` numbers.
+    pub fn symbol_neighbours(
+        &self,
+        symbol: Option<char>,
+        neighbours: Option<usize>,
+    ) -> impl Iterator<Item = SymbolNeighbours<'_>> {
+        self.symbols
+            .iter()
+            .filter(move |sym| symbol.is_none_or(|symbol| sym.sym == symbol))
+            .map(|sym| SymbolNeighbours {
+                symbol: sym,
+                nums: self.nums_adjacent_to(sym),
+            })
+            .filter(move |found| neighbours.is_none_or(|count| found.nums.len() == count))
+    }
+
+    /// Sums the `aggregate` of each matching symbol's neighbours. Part 2 is
+    /// `sum_ratios(Some('*'), Some(2), Aggregate::Product)`.
+    pub fn sum_ratios(
+        &self,
+        symbol: Option<char>,
+        neighbours: Option<usize>,
+        aggregate: Aggregate,
+    ) -> u64 {
+        self.symbol_neighbours(symbol, neighbours)
+            .map(|found| found.ratio(aggregate))
+            .sum()
+    }
 }
 
-fn is_point_adjacent_to_rect(
-    rect_topleft: &Point,
-    rect_bottomright: &Point,
-    point: &Point,
-) -> bool {
-    (point.1 >= rect_topleft.1 && point.1 <= rect_bottomright.1)
-        && (point.0 >= rect_topleft.0 && point.0 <= rect_bottomright.0)
+#[aoc_generator(day3)]
+pub fn input_generator(input: &str) -> Result<Board, ParseError> {
+    Board::from_grid(&Grid::parse(&normalise(input), |chr| chr)?)
 }
 
 #[aoc(day3, part1)]
-pub fn part1(board: &Board) -> u32 {
+pub fn part1(board: &Board) -> u64 {
     board
         .nums
         .iter()
-        .filter(|num| {
-            let top_left = (num.pos.0 - 1, num.pos.1 - 1);
-            let bottom_right = (num.pos.0 + num.length, num.pos.1 + 1);
-
-            board
-                .symbols
-                .iter()
-                .any(|symbol| is_point_adjacent_to_rect(&top_left, &bottom_right, &symbol.pos))
-        })
-        .fold(0, |sum, num| {
-            sum + num.num
-        })
+        .filter(|num| board.symbols_adjacent_to(num).next().is_some())
+        .map(|num| num.num as u64)
+        .sum()
 }
 
 #[aoc(day3, part2)]
-pub fn part2(board: &Board) -> u32 {
-    board
-        .symbols
-        .iter()
-        .filter(|sym| sym.sym == '*')
-        .map(|symbol| {
-            let adjacent_nums: Vec<_> = board
-                .nums
-                .iter()
-                .filter(|num| {
-                    let top_left = (num.pos.0 - 1, num.pos.1 - 1);
-                    let bottom_right = (num.pos.0 + num.length, num.pos.1 + 1);
-
-                    is_point_adjacent_to_rect(&top_left, &bottom_right, &symbol.pos)
-                })
-                .map(|num| num.num)
-                .collect();
-
-            if adjacent_nums.len() == 2 {
-                Some(adjacent_nums[0] * adjacent_nums[1])
-            } else {
-                None
-            }
-        })
-        .filter(|maybe_nums| maybe_nums.is_some())
-        .map(|maybe_nums| maybe_nums.unwrap())
-        .sum()
+pub fn part2(board: &Board) -> u64 {
+    board.sum_ratios(Some('*'), Some(2), Aggregate::Product)
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u32 = 3;
+    const NAME: &'static str = "Gear Ratios";
+
+    type Input = Board;
+    type Output1 = u64;
+    type Output2 = u64;
+    type Error = ParseError;
+
+    fn parse(input: &str) -> Result<Board, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(board: &Board) -> u64 {
+        part1(board)
+    }
+
+    fn part2(board: &Board) -> u64 {
+        part2(board)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::{fixture, fixture_tests};
     use indoc::indoc;
 
+    fixture_tests!(3, Day3);
+
     #[test]
     fn test_parse() {
-        let board = input_generator(indoc! {
-            "
-            467..114..
-            ...*......
-            ..35..633.
-            ......#...
-            617*......
-            .....+.58.
-            ..592.....
-            ......755.
-            ...$.*....
-            .664.598..
-            "
-        });
+        let board = input_generator(&fixture(3, "example")).unwrap();
 
         assert_eq!(board.nums.len(), 10);
         assert_eq!(board.symbols.len(), 6);
-        assert_eq!(board.nums[3].pos, (6, 2));
+        assert_eq!(board.nums[3].pos, Point::new(6, 2));
         assert_eq!(board.nums[3].length, 3);
-        assert_eq!(board.symbols[2].pos, (3, 4));
+        assert_eq!(board.symbols[2].pos, Point::new(3, 4));
     }
 
     #[test]
-    fn test_part1() {
+    fn test_large_numbers() {
+        let input: String = (0..10)
+            .map(|i| format!("{}*{}.\n", u32::MAX - i, 300 + i))
+            .collect();
+        let board = input_generator(&input).unwrap();
+
+        assert_eq!(board.nums.len(), 20);
+        assert_eq!(board.nums[18].num, u32::MAX - 9);
+        assert_eq!(board.nums[19].num, 309);
+        assert_eq!(part1(&board), 10 * (u32::MAX as u64 + 300));
+
+        let err = input_generator("1.4294967296\n").err().unwrap();
+        assert_eq!((err.line, err.column), (1, 3));
+        assert_eq!(err.expected, "a number that fits in a u32");
+    }
+
+    #[test]
+    fn test_adjacency() {
         let board = input_generator(indoc! {
             "
-            467..114..
-            ...*......
-            ..35..633.
-            ......#...
-            617*......
-            .....+.58.
-            ..592.....
-            ......755.
-            ...$.*....
-            .664.598..
+            12.......
+            ..*..#...
+            .345...7.
+            .....8*..
             "
-        });
+        })
+        .unwrap();
 
-        assert_eq!(part1(&board), 4361);
+        let star = &board.symbols[0];
+        let adjacent: Vec<_> = board
+            .nums_adjacent_to(star)
+            .iter()
+            .map(|num| num.num)
+            .collect();
+        assert_eq!(adjacent, vec![12, 345]);
+
+        let hash = &board.symbols[1];
+        assert!(board.nums_adjacent_to(hash).is_empty());
+
+        let seven = board.nums.iter().find(|num| num.num == 7).unwrap();
+        let symbols: Vec<_> = board
+            .symbols_adjacent_to(seven)
+            .map(|sym| sym.sym)
+            .collect();
+        assert_eq!(symbols, vec!['*']);
+
+        let eight = board.nums.iter().find(|num| num.num == 8).unwrap();
+        assert_eq!(board.symbols_adjacent_to(eight).count(), 1);
     }
 
     #[test]
-    fn test_part2() {
+    fn test_sum_ratios() {
         let board = input_generator(indoc! {
             "
-            467..114..
-            ...*......
-            ..35..633.
-            ......#...
-            617*......
-            .....+.58.
-            ..592+....
-            ......755.
-            ...$.*....
-            .664.598..
+            12...3...
+            ..#.9#...
+            .345.4.7.
+            .....8*..
             "
-        });
+        })
+        .unwrap();
+
+        assert_eq!(
+            board.sum_ratios(Some('#'), Some(3), Aggregate::Product),
+            3 * 9 * 4
+        );
+        assert_eq!(
+            board.sum_ratios(Some('#'), Some(2), Aggregate::Sum),
+            12 + 345
+        );
+        assert_eq!(board.sum_ratios(Some('*'), None, Aggregate::Sum), 4 + 7 + 8);
+        assert_eq!(board.sum_ratios(Some('$'), None, Aggregate::Sum), 0);
+        assert_eq!(board.symbol_neighbours(None, Some(3)).count(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let board = input_generator("12.\n..*\n").unwrap();
 
-        assert_eq!(part2(&board), 467835);
+        assert_eq!(
+            serde_json::to_value(&board).unwrap(),
+            serde_json::json!({
+                "symbols": [{ "sym": "*", "pos": { "x": 2, "y": 1 } }],
+                "nums": [{ "num": 12, "length": 2, "pos": { "x": 0, "y": 0 } }],
+            })
+        );
     }
 }
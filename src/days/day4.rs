@@ -1,164 +1,518 @@
-use std::cmp::min;
+use std::ops::Range;
 
 use nom::{
-    bytes::complete::tag,
-    character::complete::{char, line_ending, space1, u8},
-    multi::{separated_list0, separated_list1},
-    sequence::tuple,
-    IResult, Parser,
+    character::complete::space1,
+    multi::separated_list1,
+    sequence::{preceded, tuple},
+    Parser,
 };
 
+use crate::{
+    parser::{labelled, lines, normalise, number, parse_all, IResult, ParseError, SyntaxError},
+    solution::Solution,
+};
+
+/// A set of scratchcard numbers in `0..1024`, stored as one bit per number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberSet([u128; NumberSet::WORDS]);
+
+impl NumberSet {
+    pub const CAPACITY: u16 = 1024;
+    const WORD_BITS: u16 = u128::BITS as u16;
+    const WORDS: usize = (Self::CAPACITY / Self::WORD_BITS) as usize;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `num` to the set. Panics if `num` is not below
+    /// [`NumberSet::CAPACITY`].
+    pub fn insert(&mut self, num: u16) {
+        assert!(num < Self::CAPACITY, "{num} does not fit in a NumberSet");
+        let (word, bit) = Self::position(num);
+        self.0[word] |= 1 << bit;
+    }
+
+    pub fn contains(&self, num: u16) -> bool {
+        if num >= Self::CAPACITY {
+            return false;
+        }
+        let (word, bit) = Self::position(num);
+        self.0[word] & (1 << bit) != 0
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub fn intersection(&self, other: &NumberSet) -> NumberSet {
+        NumberSet(std::array::from_fn(|word| self.0[word] & other.0[word]))
+    }
+
+    /// How many numbers are in both sets, without building the intersection.
+    pub fn intersection_len(&self, other: &NumberSet) -> u32 {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .map(|(a, b)| (a & b).count_ones())
+            .sum()
+    }
+
+    /// The numbers in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u16> {
+        let words = self.0;
+        (0..Self::CAPACITY).filter(move |&num| {
+            let (word, bit) = Self::position(num);
+            words[word] & (1 << bit) != 0
+        })
+    }
+
+    fn position(num: u16) -> (usize, u16) {
+        ((num / Self::WORD_BITS) as usize, num % Self::WORD_BITS)
+    }
+}
+
+impl Default for NumberSet {
+    fn default() -> Self {
+        NumberSet([0; Self::WORDS])
+    }
+}
+
+/// Serialized as its numbers in ascending order.
+#[cfg(feature = "serde")]
+impl serde::Serialize for NumberSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl FromIterator<u16> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = u16>>(iter: I) -> Self {
+        let mut set = NumberSet::new();
+        for num in iter {
+            set.insert(num);
+        }
+        set
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Card {
     pub id: u32,
-    pub winners: Vec<u32>,
-    pub numbers: Vec<u32>,
+    winners: NumberSet,
+    numbers: NumberSet,
+    /// Cached by [`Card::new`]. The number sets are private so that it
+    /// cannot go stale.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    matches: u32,
+}
+
+impl Card {
+    pub fn new(id: u32, winners: NumberSet, numbers: NumberSet) -> Self {
+        let matches = winners.intersection_len(&numbers);
+
+        Card {
+            id,
+            winners,
+            numbers,
+            matches,
+        }
+    }
+
+    pub fn winners(&self) -> &NumberSet {
+        &self.winners
+    }
+
+    pub fn numbers(&self) -> &NumberSet {
+        &self.numbers
+    }
+
+    /// How many of the card's numbers are winners.
+    pub fn matches(&self) -> u32 {
+        self.matches
+    }
+
+    /// The card's part 1 score: one point for the first match, doubled for
+    /// every match after it. Saturates at `u64::MAX` for cards with more
+    /// than 64 matches.
+    pub fn points(&self) -> u64 {
+        match self.matches {
+            0 => 0,
+            matches => 1u64.checked_shl(matches - 1).unwrap_or(u64::MAX),
+        }
+    }
 }
 
-fn parse_card(input: &str) -> IResult<&str, Card> {
+fn parse_card_number(input: &str) -> IResult<'_, u16> {
+    let (rest, num) = number(input)?;
+
+    // Fail outright rather than letting the surrounding list end early.
+    if num >= NumberSet::CAPACITY {
+        return Err(nom::Err::Failure(SyntaxError::new(
+            input,
+            "a number below 1024",
+        )));
+    }
+
+    Ok((rest, num))
+}
+
+fn card_numbers(input: &str) -> IResult<'_, Vec<u16>> {
+    separated_list1(space1, parse_card_number)(input)
+}
+
+fn parse_card(input: &str) -> IResult<'_, Card> {
     tuple((
-        tag("Card"),
-        space1,
-        u8,
-        char(':'),
-        space1,
-        separated_list1(space1, u8),
-        space1,
-        char('|'),
-        space1,
-        separated_list1(space1, u8),
+        labelled("Card", number),
+        labelled(":", card_numbers),
+        preceded(space1, labelled("|", card_numbers)),
     ))
-    .map(|(_, _, id, _, _, winners, _, _, _, numbers)| Card {
-        id: id as u32,
-        winners: winners.into_iter().map(|num| num as u32).collect(),
-        numbers: numbers.into_iter().map(|num| num as u32).collect(),
+    .map(|(id, winners, numbers)| {
+        Card::new(
+            id,
+            winners.into_iter().collect(),
+            numbers.into_iter().collect(),
+        )
     })
     .parse(input)
 }
 
 #[aoc_generator(day4)]
-pub fn input_generator(input: &str) -> Vec<Card> {
-    let (_, cards) = separated_list0(line_ending, parse_card)
-        .parse(input)
-        .unwrap();
-
-    cards
+pub fn input_generator(input: &str) -> Result<Vec<Card>, ParseError> {
+    let input: &str = &normalise(input);
+    parse_all(input, lines(parse_card))
 }
 
 #[aoc(day4, part1)]
-pub fn part1(cards: &Vec<Card>) -> u32 {
-    let mut total_score: u32 = 0;
-
-    for card in cards {
-        let mut score: u32 = 0;
-
-        for num in &card.numbers {
-            if card.winners.contains(&num) {
-                if score == 0 {
-                    score = 1;
-                } else {
-                    score *= 2;
-                }
-            }
-        }
+pub fn part1(cards: &[Card]) -> u64 {
+    cards.iter().map(Card::points).fold(0, u64::saturating_add)
+}
+
+/// The indices of the cards won by the card at `index`. Copies that would
+/// run past the end of the table are not won.
+fn copied_range(cards: &[Card], index: usize) -> Range<usize> {
+    let first_copied = index + 1;
+    let last_copied = (first_copied + cards[index].matches() as usize).min(cards.len());
+
+    first_copied..last_copied
+}
+
+/// How many instances of each card are held once all copies are won,
+/// counting the original. The counts grow exponentially along chains of
+/// winning cards, so they saturate at `u64::MAX`.
+fn count_copies(cards: &[Card]) -> Vec<u64> {
+    let mut copies: Vec<u64> = vec![1; cards.len()];
 
-        total_score += score;
+    for index in 0..cards.len() {
+        for i in copied_range(cards, index) {
+            copies[i] = copies[i].saturating_add(copies[index]);
+        }
     }
 
-    total_score
+    copies
 }
 
 #[aoc(day4, part2)]
-pub fn part2(cards: &Vec<Card>) -> u32 {
-    let mut cache: Vec<Option<u32>> = vec![None; cards.len()];
-    let mut sum: u32 = 0;
+pub fn part2(cards: &[Card]) -> u64 {
+    count_copies(cards).into_iter().fold(0, u64::saturating_add)
+}
 
-    for i in 0..cards.len() {
-        sum += calculate_card_score(cards, i, &mut cache);
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+    const NAME: &'static str = "Scratchcards";
+
+    type Input = Vec<Card>;
+    type Output1 = u64;
+    type Output2 = u64;
+    type Error = ParseError;
+
+    fn parse(input: &str) -> Result<Vec<Card>, ParseError> {
+        input_generator(input)
     }
 
-    sum
-}
+    fn part1(cards: &Vec<Card>) -> u64 {
+        part1(cards)
+    }
 
-fn calculate_card_score(cards: &Vec<Card>, index: usize, cache: &mut Vec<Option<u32>>) -> u32 {
-    if let Some(cached_score) = cache.get(index).unwrap() {
-        return *cached_score;
+    fn part2(cards: &Vec<Card>) -> u64 {
+        part2(cards)
     }
+}
 
-    let card = cards.get(index).unwrap();
+/// How a single card contributes to both parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardReport {
+    pub id: u32,
+    /// The card's winning numbers that it also has, in ascending order.
+    pub matching: Vec<u16>,
+    pub points: u64,
+    /// Instances of this card held at the end, including the original.
+    pub copies: u64,
+    /// The IDs of the cards that each instance of this card wins a copy of.
+    pub copied_ids: Vec<u32>,
+}
 
-    let num_winners = card
-        .numbers
+pub fn analyse(cards: &[Card]) -> Vec<CardReport> {
+    cards
         .iter()
-        .filter(|num| card.winners.contains(num))
-        .count();
+        .zip(count_copies(cards))
+        .enumerate()
+        .map(|(index, (card, copies))| CardReport {
+            id: card.id,
+            matching: card.winners().intersection(card.numbers()).iter().collect(),
+            points: card.points(),
+            copies,
+            copied_ids: copied_range(cards, index).map(|i| cards[i].id).collect(),
+        })
+        .collect()
+}
 
-    let mut card_score = 1;
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    let next_index = index + 1;
-    let last_copied_index = min(next_index + num_winners, cards.len() - 1);
+/// Renders `reports` as a plain text table with one row per card.
+pub fn render_report(reports: &[CardReport]) -> String {
+    let header = ["Card", "Matching", "Points", "Copies", "Copies cards"];
+    let rows: Vec<[String; 5]> = reports
+        .iter()
+        .map(|report| {
+            [
+                report.id.to_string(),
+                join(&report.matching),
+                report.points.to_string(),
+                report.copies.to_string(),
+                join(&report.copied_ids),
+            ]
+        })
+        .collect();
 
-    for i in next_index..last_copied_index {
-        card_score += calculate_card_score(cards, i, cache);
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
     }
 
-    *cache.get_mut(index).unwrap() = Some(card_score);
+    let format_row = |cells: [&str; 5]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
 
-    card_score
+    let mut table = vec![
+        format_row(header),
+        widths.map(|width| "-".repeat(width)).join("-+-"),
+    ];
+    table.extend(
+        rows.iter()
+            .map(|row| format_row(row.each_ref().map(String::as_str))),
+    );
+
+    table.join("\n") + "\n"
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::{fixture, fixture_tests};
     use indoc::indoc;
 
+    fixture_tests!(4, Day4);
+
     #[test]
     pub fn test_parse() {
-        let cards = input_generator(indoc! {
-            "
-            Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-            Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-            Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-            Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-            Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-            Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
-            "
-        });
+        let cards = input_generator(&fixture(4, "example")).unwrap();
 
         assert_eq!(cards.len(), 6);
         assert_eq!(cards[0].id, 1);
-        assert_eq!(cards[0].winners, vec![41, 48, 83, 86, 17]);
-        assert_eq!(cards[0].numbers, vec![83, 86, 6, 31, 17, 9, 48, 53]);
+        assert_eq!(
+            cards[0].winners().iter().collect::<Vec<_>>(),
+            vec![17, 41, 48, 83, 86]
+        );
+        assert_eq!(
+            cards[0].numbers().iter().collect::<Vec<_>>(),
+            vec![6, 9, 17, 31, 48, 53, 83, 86]
+        );
+        assert_eq!(cards[0].matches(), 4);
+    }
+
+    #[test]
+    pub fn test_number_set() {
+        let set: NumberSet = [0, 5, 64, 127, 128, 300, 1023].into_iter().collect();
+
+        assert_eq!(set.len(), 7);
+        assert!(set.contains(64) && set.contains(128) && set.contains(1023));
+        assert!(!set.contains(6) && !set.contains(200) && !set.contains(u16::MAX));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![0, 5, 64, 127, 128, 300, 1023]
+        );
+
+        let other: NumberSet = [5, 127, 3, 1023].into_iter().collect();
+        let common = set.intersection(&other);
+        assert_eq!(common.iter().collect::<Vec<_>>(), vec![5, 127, 1023]);
+        assert_eq!(common, [1023, 127, 5].into_iter().collect());
+        assert_eq!(set.intersection_len(&other), 3);
+        assert!(NumberSet::new().is_empty());
+        assert!(set.intersection(&[1000].into_iter().collect()).is_empty());
     }
 
     #[test]
-    pub fn test_part1() {
+    pub fn test_parse_number_too_large() {
+        let err = input_generator("Card 1: 41 65536 | 83 86\n").err().unwrap();
+
+        assert_eq!((err.line, err.column), (1, 12));
+        assert_eq!(err.expected, "a number that fits in a u16");
+
+        let err = input_generator("Card 1: 41 1024 | 83 86\n").err().unwrap();
+
+        assert_eq!((err.line, err.column), (1, 12));
+        assert_eq!(err.expected, "a number below 1024");
+
+        let err = input_generator("Card 4294967296: 41 | 83 86\n")
+            .err()
+            .unwrap();
+
+        assert_eq!((err.line, err.column), (1, 6));
+        assert_eq!(err.expected, "a number that fits in a u32");
+    }
+
+    #[test]
+    pub fn test_parse_large_numbers() {
+        let input: String = (250..260)
+            .map(|id| format!("Card {id}: 1 {} {} | {} 2 3\n", id * 3, id + 1, id * 3))
+            .collect();
+        let cards = input_generator(&input).unwrap();
+
+        assert_eq!(cards.len(), 10);
+        assert_eq!(cards[9].id, 259);
+        assert_eq!(
+            cards[9].winners().iter().collect::<Vec<_>>(),
+            vec![1, 260, 777]
+        );
+        assert_eq!(cards[9].matches(), 1);
+        assert_eq!(part1(&cards), 10);
+    }
+
+    #[test]
+    pub fn test_analyse() {
+        let cards = input_generator(&fixture(4, "example")).unwrap();
+
+        let reports = analyse(&cards);
+
+        assert_eq!(
+            reports[1],
+            CardReport {
+                id: 2,
+                matching: vec![32, 61],
+                points: 2,
+                copies: 2,
+                copied_ids: vec![3, 4],
+            }
+        );
+        assert_eq!(reports.iter().map(|r| r.copies).sum::<u64>(), part2(&cards));
+        assert_eq!(reports.iter().map(|r| r.points).sum::<u64>(), part1(&cards));
+
+        assert_eq!(
+            render_report(&reports[..2]),
+            indoc! {
+                "
+                Card | Matching    | Points | Copies | Copies cards
+                -----+-------------+--------+--------+-------------
+                1    | 17 48 83 86 | 8      | 1      | 2 3 4 5
+                2    | 32 61       | 2      | 2      | 3 4
+                "
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_points_past_64_matches() {
+        let many: NumberSet = (1..=64).collect();
+        let card = Card::new(1, many, many);
+        assert_eq!(card.points(), 1 << 63);
+
+        let more: NumberSet = (1..=70).collect();
+        let cards = vec![Card::new(1, more, more), card];
+        assert_eq!(cards[0].points(), u64::MAX);
+        assert_eq!(part1(&cards), u64::MAX);
+    }
+
+    #[test]
+    pub fn test_part2_copies_last_card() {
         let cards = input_generator(indoc! {
             "
-            Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-            Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-            Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-            Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-            Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-            Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+            Card 1: 1 2 | 1 2
+            Card 2: 5 6 | 5 9
+            Card 3: 7 8 | 9 9
             "
-        });
+        })
+        .unwrap();
 
-        assert_eq!(part1(&cards), 13);
+        // Card 3 is won once by card 1 and twice by the copies of card 2.
+        assert_eq!(part2(&cards), 1 + 2 + 4);
     }
 
     #[test]
-    pub fn test_part2() {
+    pub fn test_part2_long_chain() {
+        let chain = |len: u32| -> Vec<Card> {
+            let input: String = (1..=len)
+                .map(|id| format!("Card {id}: 1 2 3 | 1 2 3\n"))
+                .collect();
+            input_generator(&input).unwrap()
+        };
+
+        // Each card after the third is held once, plus once for every
+        // instance of the three cards before it.
+        let copies = count_copies(&chain(40));
+        assert_eq!(copies[..6], [1, 2, 4, 8, 15, 28]);
+        assert_eq!(part2(&chain(40)), copies.iter().sum::<u64>());
+        assert!(copies[39] > u32::MAX as u64);
+
+        assert_eq!(count_copies(&chain(100))[99], u64::MAX);
+        assert_eq!(part2(&chain(100)), u64::MAX);
+    }
+
+    #[test]
+    pub fn test_part2_copies_past_end() {
         let cards = input_generator(indoc! {
             "
-            Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-            Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-            Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-            Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-            Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-            Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+            Card 1: 1 2 3 | 1 2 3
+            Card 2: 4 5 6 | 4 5 9
             "
-        });
+        })
+        .unwrap();
+
+        assert_eq!(part2(&cards), 1 + 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_serialize() {
+        let cards = input_generator("Card 3: 41 300 | 300 2 7\n").unwrap();
 
-        assert_eq!(part2(&cards), 30);
+        assert_eq!(
+            serde_json::to_value(&cards).unwrap(),
+            serde_json::json!([
+                { "id": 3, "winners": [41, 300], "numbers": [2, 7, 300] }
+            ])
+        );
     }
 }
@@ -1,147 +1,812 @@
+use std::{error::Error, fmt, ops::Range};
+
 use nom::{
-    bytes::complete::tag,
-    character::complete::{char, line_ending, none_of, space1, u64},
-    multi::{many1, separated_list1},
-    sequence::tuple,
-    IResult, Parser,
+    character::complete::{alpha1, line_ending, space1},
+    multi::separated_list1,
+    sequence::{separated_pair, tuple},
+    Parser,
+};
+
+use crate::{
+    parser::{
+        blank_lines, blocks, labelled_numbers, normalise, number, parse_all, tag, IResult,
+        ParseError, SyntaxError,
+    },
+    solution::Solution,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Mapping {
     pub source_start: u64,
     pub dest_start: u64,
     pub length: u64,
 }
 
+impl Mapping {
+    pub fn source_end(&self) -> u64 {
+        self.source_start + self.length
+    }
+
+    fn map(&self, value: u64) -> u64 {
+        self.dest_start + (value - self.source_start)
+    }
+
+    fn is_identity(&self) -> bool {
+        self.source_start == self.dest_start
+    }
+}
+
+/// A mapping table whose source ranges are sorted and never overlap, so a
+/// value is looked up with a single binary search. Values outside every
+/// range map to themselves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MappingTable {
+    mappings: Vec<Mapping>,
+}
+
+impl MappingTable {
+    /// Builds a table from mappings in puzzle order. Where source ranges
+    /// overlap, the mapping listed first wins, matching how the almanac is
+    /// read.
+    pub fn new(mappings: &[Mapping]) -> Self {
+        let mut accepted: Vec<Mapping> = vec![];
+
+        for mapping in mappings {
+            let mut pieces = vec![*mapping];
+            for taken in &accepted {
+                pieces = pieces
+                    .into_iter()
+                    .flat_map(|piece| {
+                        let before = piece.source_start..piece.source_end().min(taken.source_start);
+                        let after = piece.source_start.max(taken.source_end())..piece.source_end();
+                        [before, after]
+                            .into_iter()
+                            .filter(|range| !range.is_empty())
+                            .map(move |range| Mapping {
+                                source_start: range.start,
+                                dest_start: piece.map(range.start),
+                                length: range.end - range.start,
+                            })
+                    })
+                    .collect();
+            }
+            accepted.extend(pieces);
+        }
+
+        Self::from_disjoint(accepted)
+    }
+
+    /// Sorts non-overlapping mappings, dropping identity and empty ones and
+    /// merging neighbours that continue each other.
+    fn from_disjoint(mut mappings: Vec<Mapping>) -> Self {
+        mappings.retain(|mapping| mapping.length > 0 && !mapping.is_identity());
+        mappings.sort_by_key(|mapping| mapping.source_start);
+
+        let mut merged: Vec<Mapping> = vec![];
+        for mapping in mappings {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source_end() == mapping.source_start
+                        && last.dest_start + last.length == mapping.dest_start =>
+                {
+                    last.length += mapping.length;
+                }
+                _ => merged.push(mapping),
+            }
+        }
+
+        MappingTable { mappings: merged }
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    pub fn get(&self, value: u64) -> u64 {
+        let index = self
+            .mappings
+            .partition_point(|mapping| mapping.source_end() <= value);
+
+        match self.mappings.get(index) {
+            Some(mapping) if mapping.source_start <= value => mapping.map(value),
+            _ => value,
+        }
+    }
+
+    /// Splits `range` into pieces that each map linearly, returning every
+    /// piece with the destination of its first value. Gaps between mappings
+    /// come back as identity pieces.
+    fn pieces(&self, range: Range<u64>) -> Vec<(Range<u64>, u64)> {
+        let mut pieces = vec![];
+        let mut start = range.start;
+        let first = self
+            .mappings
+            .partition_point(|mapping| mapping.source_end() <= start);
+
+        for mapping in &self.mappings[first..] {
+            if start >= range.end {
+                break;
+            }
+            if start < mapping.source_start {
+                let end = mapping.source_start.min(range.end);
+                pieces.push((start..end, start));
+                start = end;
+            }
+            if start < range.end {
+                let end = mapping.source_end().min(range.end);
+                pieces.push((start..end, mapping.map(start)));
+                start = end;
+            }
+        }
+
+        if start < range.end {
+            pieces.push((start..range.end, start));
+        }
+
+        pieces
+    }
+
+    /// Every value that maps to `value`. There may be none or several when
+    /// the table is not invertible.
+    pub fn preimages(&self, value: u64) -> Vec<u64> {
+        let mut preimages: Vec<u64> = self
+            .pieces(0..u64::MAX)
+            .into_iter()
+            .filter(|(source, dest)| *dest <= value && value - dest < source.end - source.start)
+            .map(|(source, dest)| source.start + (value - dest))
+            .collect();
+
+        preimages.sort_unstable();
+        preimages
+    }
+
+    /// The table that applies `self` and then `next`.
+    pub fn compose(&self, next: &MappingTable) -> MappingTable {
+        let mut composed = vec![];
+
+        // Walk every linear piece of `self`, identity gaps included.
+        for (source, dest) in self.pieces(0..u64::MAX) {
+            let dest_range = dest..dest + (source.end - source.start);
+
+            for (through, final_dest) in next.pieces(dest_range) {
+                composed.push(Mapping {
+                    source_start: source.start + (through.start - dest),
+                    dest_start: final_dest,
+                    length: through.end - through.start,
+                });
+            }
+        }
+
+        Self::from_disjoint(composed)
+    }
+}
+
+/// Writes the table in the almanac's `dest source length` line format.
+impl fmt::Display for MappingTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for mapping in &self.mappings {
+            writeln!(
+                f,
+                "{} {} {}",
+                mapping.dest_start, mapping.source_start, mapping.length
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapKind {
+    Source,
+    Destination,
+}
+
+/// Two mappings in the same stage that cover some of the same values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    /// Index of the stage in [`Almanac::maps`].
+    pub stage: usize,
+    /// Indices of the two mappings within the stage, in puzzle order.
+    pub first: usize,
+    pub second: usize,
+    pub kind: OverlapKind,
+    /// The shared source or destination values.
+    pub range: Range<u64>,
+}
+
+/// One `<source>-to-<dest> map:` section of the almanac.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Stage {
+    pub source: String,
+    pub dest: String,
+    pub mappings: Vec<Mapping>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Almanac {
     pub seeds: Vec<u64>,
-    pub maps: Vec<Vec<Mapping>>,
+    /// The stages in order. Each stage's `dest` is the next stage's `source`.
+    pub maps: Vec<Stage>,
 }
 
-fn parse_seeds_section(input: &str) -> IResult<&str, Vec<u64>> {
-    tuple((tag("seeds:"), space1, separated_list1(space1, u64)))
-        .map(|(_, _, seeds)| seeds)
-        .parse(input)
+impl Almanac {
+    /// The categories in conversion order, starting with the first stage's
+    /// source.
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.maps
+            .first()
+            .map(|stage| stage.source.as_str())
+            .into_iter()
+            .chain(self.maps.iter().map(|stage| stage.dest.as_str()))
+    }
+
+    /// Composes the stages that convert `from` into `to`, such as `soil` into
+    /// `humidity`. Returns `None` unless both are categories and `to` does
+    /// not come before `from`.
+    pub fn converter(&self, from: &str, to: &str) -> Option<MappingTable> {
+        let first = self.categories().position(|category| category == from)?;
+        let last = self.categories().position(|category| category == to)?;
+
+        (first <= last).then(|| {
+            self.maps[first..last]
+                .iter()
+                .fold(MappingTable::default(), |table, stage| {
+                    table.compose(&MappingTable::new(&stage.mappings))
+                })
+        })
+    }
+
+    /// Every seed that ends up at `location`, found by walking the stages
+    /// backwards.
+    pub fn seeds_for_location(&self, location: u64) -> Vec<u64> {
+        let mut values = vec![location];
+
+        for stage in self.maps.iter().rev() {
+            let table = MappingTable::new(&stage.mappings);
+            values = values
+                .into_iter()
+                .flat_map(|value| table.preimages(value))
+                .collect();
+        }
+
+        values.sort_unstable();
+        values.dedup();
+        values
+    }
+
+    /// The lowest location reachable from any of `seed_ranges`. The linear
+    /// pieces of the flattened table are tried in order of location, so the
+    /// search stops at the first piece that any seed range reaches.
+    pub fn lowest_location(&self, seed_ranges: &[Range<u64>]) -> Option<u64> {
+        let mut pieces = self.flatten().pieces(0..u64::MAX);
+        pieces.sort_by_key(|&(_, dest)| dest);
+
+        let mut lowest: Option<u64> = None;
+
+        for (source, dest) in pieces {
+            if lowest.is_some_and(|lowest| lowest <= dest) {
+                break;
+            }
+
+            for seeds in seed_ranges {
+                let start = seeds.start.max(source.start);
+                if start < seeds.end.min(source.end) {
+                    let location = dest + (start - source.start);
+                    lowest = Some(lowest.map_or(location, |lowest| lowest.min(location)));
+                }
+            }
+        }
+
+        lowest
+    }
+
+    /// Finds pairs of mappings within a stage whose source ranges overlap,
+    /// which makes the almanac ambiguous, or whose destination ranges
+    /// overlap, which makes it non-invertible.
+    pub fn overlaps(&self) -> Vec<Overlap> {
+        let mut overlaps = vec![];
+
+        for (stage, Stage { mappings, .. }) in self.maps.iter().enumerate() {
+            for (first, a) in mappings.iter().enumerate() {
+                for (second, b) in mappings.iter().enumerate().skip(first + 1) {
+                    let ranges = [
+                        (
+                            OverlapKind::Source,
+                            a.source_start..a.source_end(),
+                            b.source_start..b.source_end(),
+                        ),
+                        (
+                            OverlapKind::Destination,
+                            a.dest_start..a.dest_start + a.length,
+                            b.dest_start..b.dest_start + b.length,
+                        ),
+                    ];
+
+                    for (kind, a, b) in ranges {
+                        let range = a.start.max(b.start)..a.end.min(b.end);
+                        if !range.is_empty() {
+                            overlaps.push(Overlap {
+                                stage,
+                                first,
+                                second,
+                                kind,
+                                range,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        overlaps
+    }
+
+    /// Composes every stage into one seed-to-location table.
+    pub fn flatten(&self) -> MappingTable {
+        self.maps
+            .iter()
+            .fold(MappingTable::default(), |table, stage| {
+                table.compose(&MappingTable::new(&stage.mappings))
+            })
+    }
 }
 
-fn parse_mapping(input: &str) -> IResult<&str, Mapping> {
-    tuple((u64, space1, u64, space1, u64))
-        .map(|(dest_start, _, source_start, _, length)| Mapping {
+fn parse_mapping(input: &str) -> IResult<'_, Mapping> {
+    let (rest, (dest_start, _, source_start, _, length)) =
+        tuple((number::<u64>, space1, number::<u64>, space1, number::<u64>)).parse(input)?;
+
+    if source_start.checked_add(length).is_none() || dest_start.checked_add(length).is_none() {
+        return Err(nom::Err::Failure(SyntaxError::new(
+            input,
+            "a range that ends within a u64",
+        )));
+    }
+
+    Ok((
+        rest,
+        Mapping {
             dest_start,
             source_start,
             length,
-        })
-        .parse(input)
+        },
+    ))
 }
 
-fn parse_mapping_section(input: &str) -> IResult<&str, Vec<Mapping>> {
+/// Parses a section, keeping the input at its header so that the category
+/// chain can be checked afterwards.
+fn parse_mapping_section(input: &str) -> IResult<'_, (&str, Stage)> {
     tuple((
-        many1(none_of(":")),
-        char(':'),
+        alpha1,
+        tag("-to-"),
+        alpha1,
+        tag(" map:"),
         line_ending,
         separated_list1(line_ending, parse_mapping),
     ))
-    .map(|(_, _, _, maps)| maps)
+    .map(
+        |(source, _, dest, _, _, mappings): (&str, _, &str, _, _, _)| {
+            (
+                input,
+                Stage {
+                    source: source.to_string(),
+                    dest: dest.to_string(),
+                    mappings,
+                },
+            )
+        },
+    )
     .parse(input)
 }
 
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Almanac {
-    let (_, almanac) = tuple((
-        parse_seeds_section,
-        many1(line_ending),
-        separated_list1(many1(line_ending), parse_mapping_section),
-    ))
-    .map(|(seeds, _, maps)| Almanac { seeds, maps })
-    .parse(input)
-    .unwrap();
+pub fn input_generator(input: &str) -> Result<Almanac, ParseError> {
+    let input: &str = &normalise(input);
+    let (seeds, sections) = parse_all(
+        input,
+        separated_pair(
+            labelled_numbers("seeds:"),
+            blank_lines,
+            blocks(parse_mapping_section),
+        ),
+    )?;
 
-    almanac
+    for pair in sections.windows(2) {
+        let (_, previous) = &pair[0];
+        let (header, stage) = &pair[1];
+
+        if stage.source != previous.dest {
+            return Err(ParseError::at(
+                input,
+                header,
+                format!("a `{}-to-...` map", previous.dest),
+            ));
+        }
+    }
+
+    Ok(Almanac {
+        seeds,
+        maps: sections.into_iter().map(|(_, stage)| stage).collect(),
+    })
 }
 
 #[aoc(day5, part1)]
 pub fn part1(input: &Almanac) -> u64 {
-    let mut min_location = u64::MAX;
-
-    for &seed in input.seeds.iter() {
-        let location = input.maps.iter().fold(seed, |from, mappings| {
-            let mapping = mappings.iter().find(|mapping| {
-                mapping.source_start <= from && (mapping.source_start + mapping.length > from)
-            });
-
-            if let Some(mapping) = mapping {
-                mapping.dest_start + (from - mapping.source_start)
-            } else {
-                from
+    let seed_to_location = input.flatten();
+
+    input
+        .seeds
+        .iter()
+        .map(|&seed| seed_to_location.get(seed))
+        .min()
+        .unwrap_or(u64::MAX)
+}
+
+fn map_ranges(ranges: Vec<Range<u64>>, mappings: &[Mapping]) -> Vec<Range<u64>> {
+    let mut unmapped = ranges;
+    let mut mapped = vec![];
+
+    for mapping in mappings {
+        let source_end = mapping.source_start + mapping.length;
+        let mut remaining = vec![];
+
+        for range in unmapped {
+            let overlap_start = range.start.max(mapping.source_start);
+            let overlap_end = range.end.min(source_end);
+
+            if overlap_start >= overlap_end {
+                remaining.push(range);
+                continue;
             }
-        });
 
-        if min_location > location {
-            min_location = location;
+            mapped.push(
+                mapping.dest_start + (overlap_start - mapping.source_start)
+                    ..mapping.dest_start + (overlap_end - mapping.source_start),
+            );
+
+            if range.start < overlap_start {
+                remaining.push(range.start..overlap_start);
+            }
+            if overlap_end < range.end {
+                remaining.push(overlap_end..range.end);
+            }
         }
+
+        unmapped = remaining;
     }
 
-    min_location
+    mapped.extend(unmapped);
+    mapped
+}
+
+/// Part 2 reads the seeds as `(start, length)` pairs, but there was an odd
+/// number of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnpairedSeedError {
+    /// The last seed, which has no length.
+    pub start: u64,
+}
+
+impl fmt::Display for UnpairedSeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed range starting at {} has no length", self.start)
+    }
+}
+
+impl Error for UnpairedSeedError {}
+
+#[aoc(day5, part2)]
+pub fn part2(input: &Almanac) -> Result<u64, UnpairedSeedError> {
+    let pairs = input.seeds.chunks_exact(2);
+    if let [start] = *pairs.remainder() {
+        return Err(UnpairedSeedError { start });
+    }
+
+    let seed_ranges = pairs
+        // A range running past `u64::MAX` is cut short there, which is as
+        // far as any mapping can reach.
+        .map(|pair| pair[0]..pair[0].saturating_add(pair[1]))
+        .collect();
+
+    Ok(input
+        .maps
+        .iter()
+        .fold(seed_ranges, |ranges, stage| {
+            map_ranges(ranges, &stage.mappings)
+        })
+        .into_iter()
+        .map(|range| range.start)
+        .min()
+        .unwrap_or(u64::MAX))
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u32 = 5;
+    const NAME: &'static str = "If You Give A Seed A Fertilizer";
+
+    type Input = Almanac;
+    type Output1 = u64;
+    type Output2 = Result<u64, UnpairedSeedError>;
+    type Error = ParseError;
+
+    fn parse(input: &str) -> Result<Almanac, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(almanac: &Almanac) -> u64 {
+        part1(almanac)
+    }
+
+    fn part2(almanac: &Almanac) -> Result<u64, UnpairedSeedError> {
+        part2(almanac)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::{fixture, fixture_tests};
     use indoc::indoc;
 
+    fixture_tests!(5, Day5);
+
     fn example() -> Almanac {
-        input_generator(indoc! {
-            "
-            seeds: 79 14 55 13
+        input_generator(&fixture(5, "example")).unwrap()
+    }
 
-            seed-to-soil map:
-            50 98 2
-            52 50 48
+    #[test]
+    pub fn test_parse() {
+        let almanac = example();
 
-            soil-to-fertilizer map:
-            0 15 37
-            37 52 2
-            39 0 15
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.maps.len(), 7);
+        assert_eq!(almanac.maps[2].source, "fertilizer");
+        assert_eq!(almanac.maps[2].dest, "water");
+        assert_eq!(almanac.maps[2].mappings.len(), 4);
+        assert_eq!(almanac.maps[2].mappings[0].dest_start, 49);
+        assert_eq!(almanac.maps[2].mappings[0].source_start, 53);
+        assert_eq!(almanac.maps[2].mappings[0].length, 8);
+    }
 
-            fertilizer-to-water map:
-            49 53 8
-            0 11 42
-            42 0 7
-            57 7 4
+    #[test]
+    pub fn test_table_overlaps() {
+        let table = MappingTable::new(&[
+            Mapping {
+                source_start: 10,
+                dest_start: 100,
+                length: 10,
+            },
+            Mapping {
+                source_start: 5,
+                dest_start: 50,
+                length: 20,
+            },
+        ]);
 
-            water-to-light map:
-            88 18 7
-            18 25 70
+        assert_eq!(table.get(4), 4);
+        assert_eq!(table.get(7), 52);
+        assert_eq!(table.get(12), 102);
+        assert_eq!(table.get(22), 67);
+        assert_eq!(table.get(25), 25);
+        assert_eq!(table.mappings().len(), 3);
+    }
+
+    #[test]
+    pub fn test_compose() {
+        let almanac = example();
+        let soil = MappingTable::new(&almanac.maps[0].mappings);
+        let fertilizer = MappingTable::new(&almanac.maps[1].mappings);
+        let composed = soil.compose(&fertilizer);
+
+        for seed in 0..200 {
+            assert_eq!(composed.get(seed), fertilizer.get(soil.get(seed)));
+        }
+    }
+
+    #[test]
+    pub fn test_flatten() {
+        let almanac = example();
+        let stages: Vec<_> = almanac
+            .maps
+            .iter()
+            .map(|stage| MappingTable::new(&stage.mappings))
+            .collect();
+        let flat = almanac.flatten();
+
+        for seed in 0..200 {
+            let expected = stages.iter().fold(seed, |value, stage| stage.get(value));
+            assert_eq!(flat.get(seed), expected, "seed {seed}");
+        }
+
+        assert_eq!(flat.get(79), 82);
+        assert_eq!(flat.to_string().lines().count(), flat.mappings().len());
+    }
+
+    #[test]
+    pub fn test_converter() {
+        let almanac = example();
+
+        assert_eq!(
+            almanac.categories().collect::<Vec<_>>(),
+            vec![
+                "seed",
+                "soil",
+                "fertilizer",
+                "water",
+                "light",
+                "temperature",
+                "humidity",
+                "location"
+            ]
+        );
+
+        // Seed 79 is soil 81, fertilizer 81, water 81, light 74,
+        // temperature 78, humidity 78 and location 82.
+        let soil_to_humidity = almanac.converter("soil", "humidity").unwrap();
+        assert_eq!(soil_to_humidity.get(81), 78);
+
+        let seed_to_location = almanac.converter("seed", "location").unwrap();
+        assert_eq!(seed_to_location, almanac.flatten());
 
-            light-to-temperature map:
-            45 77 23
-            81 45 19
-            68 64 13
+        assert_eq!(almanac.converter("water", "water").unwrap().get(5), 5);
+        assert!(almanac.converter("humidity", "soil").is_none());
+        assert!(almanac.converter("seed", "colour").is_none());
+    }
+
+    #[test]
+    pub fn test_inverse() {
+        let almanac = example();
+        let flat = almanac.flatten();
 
-            temperature-to-humidity map:
-            0 69 1
-            1 0 69
+        assert!(almanac.seeds_for_location(82).contains(&79));
+        assert!(almanac.seeds_for_location(46).contains(&82));
+
+        for location in 0..120 {
+            let seeds = almanac.seeds_for_location(location);
+            assert!(!seeds.is_empty());
+            for seed in seeds {
+                assert_eq!(flat.get(seed), location);
+            }
+        }
 
-            humidity-to-location map:
-            60 56 37
-            56 93 4
+        assert_eq!(almanac.lowest_location(&[79..93, 55..68]), Some(46));
+        assert_eq!(almanac.lowest_location(&[79..80, 13..14]), Some(35));
+        assert_eq!(almanac.lowest_location(&[]), None);
+        assert!(almanac.overlaps().is_empty());
+    }
+
+    #[test]
+    pub fn test_overlaps() {
+        let almanac = input_generator(indoc! {
+            "
+            seeds: 1
+
+            seed-to-soil map:
+            50 10 10
+            70 15 10
+            55 30 2
             "
         })
+        .unwrap();
+
+        assert_eq!(
+            almanac.overlaps(),
+            vec![
+                Overlap {
+                    stage: 0,
+                    first: 0,
+                    second: 1,
+                    kind: OverlapKind::Source,
+                    range: 15..20,
+                },
+                Overlap {
+                    stage: 0,
+                    first: 0,
+                    second: 2,
+                    kind: OverlapKind::Destination,
+                    range: 55..57,
+                },
+            ]
+        );
+
+        // 55 is reached from 15, from 30 and from itself, since it is
+        // outside every source range.
+        assert_eq!(almanac.seeds_for_location(55), vec![15, 30, 55]);
+        assert_eq!(almanac.seeds_for_location(10), Vec::<u64>::new());
     }
 
     #[test]
-    pub fn test_parse() {
-        let almanac = example();
+    pub fn test_large_numbers() {
+        let mut input = format!("seeds: 300 {}\n\nseed-to-soil map:\n", u32::MAX as u64 + 7);
+        for i in 0..10u64 {
+            input += &format!("{} {} 256\n", (i + 1) << 40, (i << 32) + 1000);
+        }
 
-        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
-        assert_eq!(almanac.maps.len(), 7);
-        assert_eq!(almanac.maps[2].len(), 4);
-        assert_eq!(almanac.maps[2][0].dest_start, 49);
-        assert_eq!(almanac.maps[2][0].source_start, 53);
-        assert_eq!(almanac.maps[2][0].length, 8);
+        let almanac = input_generator(&input).unwrap();
+
+        assert_eq!(almanac.maps[0].mappings.len(), 10);
+        assert_eq!(almanac.maps[0].mappings[9].source_start, (9 << 32) + 1000);
+        assert_eq!(almanac.flatten().get(300), 300);
+        assert_eq!(part1(&almanac), 300);
+
+        let err = input_generator(&format!("seeds: {}0\n", u64::MAX))
+            .err()
+            .unwrap();
+        assert_eq!((err.line, err.column), (1, 8));
+        assert_eq!(err.expected, "a number that fits in a u64");
+
+        let err = input_generator(&format!(
+            "seeds: 1\n\nseed-to-soil map:\n1 2 3\n0 {} 2\n",
+            u64::MAX
+        ))
+        .err()
+        .unwrap();
+        assert_eq!((err.line, err.column), (5, 1));
+        assert_eq!(err.expected, "a range that ends within a u64");
+    }
+
+    #[test]
+    pub fn test_part2_seed_range_near_max() {
+        let almanac = input_generator(&format!(
+            "seeds: {} 10 7 1\n\nseed-to-soil map:\n0 100 5\n",
+            u64::MAX - 3
+        ))
+        .unwrap();
+
+        assert_eq!(part2(&almanac), Ok(7));
+    }
+
+    #[test]
+    pub fn test_part2_unpaired_seed() {
+        let almanac = input_generator("seeds: 79 14 55\n\nseed-to-soil map:\n50 98 2\n").unwrap();
+
+        assert_eq!(part1(&almanac), 14);
+        assert_eq!(part2(&almanac), Err(UnpairedSeedError { start: 55 }));
+        assert_eq!(
+            part2(&almanac).unwrap_err().to_string(),
+            "seed range starting at 55 has no length"
+        );
+    }
+
+    #[test]
+    pub fn test_broken_chain() {
+        let err = input_generator(indoc! {
+            "
+            seeds: 79 14 55 13
+
+            seed-to-soil map:
+            50 98 2
+
+            water-to-light map:
+            88 18 7
+            "
+        })
+        .err()
+        .unwrap();
+
+        assert_eq!((err.line, err.column), (6, 1));
+        assert_eq!(err.expected, "a `soil-to-...` map");
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    pub fn test_part1() {
-        assert_eq!(part1(&example()), 35);
+    pub fn test_serialize() {
+        let almanac = input_generator("seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n").unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&almanac).unwrap(),
+            serde_json::json!({
+                "seeds": [79, 14],
+                "maps": [{
+                    "source": "seed",
+                    "dest": "soil",
+                    "mappings": [{ "source_start": 98, "dest_start": 50, "length": 2 }],
+                }],
+            })
+        );
     }
 }
@@ -1,60 +1,265 @@
+use std::{error::Error, fmt};
+
 use nom::{
-    bytes::complete::tag,
-    character::complete::{line_ending, space1, u32},
-    error::ErrorKind,
-    multi::separated_list1,
-    sequence::tuple,
-    Parser,
+    character::complete::line_ending,
+    combinator::{peek, rest},
+    sequence::{preceded, tuple},
+};
+
+use crate::{
+    parser::{labelled_numbers, normalise, parse_all, ParseError},
+    solution::Solution,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Race {
-    pub duration: u32,
-    pub record_distance: u32,
+    pub duration: u64,
+    pub record_distance: u64,
 }
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Race> {
-    let (_, result) = tuple::<_, _, (_, ErrorKind), _>((
-        tag("Time:"),
-        space1,
-        separated_list1(space1, u32),
-        line_ending,
-        tag("Distance:"),
-        space1,
-        separated_list1(space1, u32),
-    ))
-    .map(|(_, _, times, _, _, _, distances)| {
-        times
-            .into_iter()
-            .zip(distances)
-            .map(|(duration, record_distance)| Race {
-                duration,
-                record_distance,
-            })
-            .collect()
+pub fn input_generator(input: &str) -> Result<Vec<Race>, ParseError> {
+    let input: &str = &normalise(input);
+    let (times, distance_line, distances): (Vec<u64>, _, Vec<u64>) = parse_all(
+        input,
+        tuple((
+            labelled_numbers("Time:"),
+            preceded(line_ending, peek(rest)),
+            labelled_numbers("Distance:"),
+        )),
+    )?;
+
+    if times.len() != distances.len() {
+        return Err(ParseError::at(
+            input,
+            distance_line,
+            format!("{} distances, one for each time", times.len()),
+        ));
+    }
+
+    Ok(times
+        .into_iter()
+        .zip(distances)
+        .map(|(duration, record_distance)| Race {
+            duration,
+            record_distance,
+        })
+        .collect())
+}
+
+impl Race {
+    fn distance_for_hold(&self, hold: u64) -> u128 {
+        hold as u128 * (self.duration - hold) as u128
+    }
+
+    /// Counts the hold times that beat the record, i.e. the integers `h` in
+    /// `0..=duration` with `h * (duration - h) > record_distance`.
+    pub fn count_winning_holds(&self) -> u64 {
+        let duration = self.duration as u128;
+        let record = self.record_distance as u128;
+
+        let Some(discriminant) = (duration * duration).checked_sub(4 * record) else {
+            return 0;
+        };
+
+        // The smaller root is (duration - sqrt(discriminant)) / 2; nudge the
+        // integer estimate onto the first hold that strictly beats the record.
+        let mut lowest = ((duration - discriminant.isqrt()) / 2) as u64;
+        while lowest > 0 && self.distance_for_hold(lowest - 1) > record {
+            lowest -= 1;
+        }
+        while lowest <= self.duration / 2 && self.distance_for_hold(lowest) <= record {
+            lowest += 1;
+        }
+
+        if lowest > self.duration / 2 {
+            return 0;
+        }
+
+        // Distances are symmetric around duration / 2.
+        let highest = self.duration - lowest;
+        highest - lowest + 1
+    }
+}
+
+/// An answer, or a number it is built from, that does not fit in a `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError {
+    pub what: &'static str,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the {} does not fit in a u64", self.what)
+    }
+}
+
+impl Error for OverflowError {}
+
+#[aoc(day6, part1)]
+pub fn part1(races: &[Race]) -> Result<u64, OverflowError> {
+    races
+        .iter()
+        .try_fold(1u64, |product, race| {
+            product.checked_mul(race.count_winning_holds())
+        })
+        .ok_or(OverflowError {
+            what: "product of the winning hold counts",
+        })
+}
+
+/// Writes `nums` one after another, as if the spaces between them were
+/// removed.
+fn concat_digits<I>(mut nums: I) -> Option<u64>
+where
+    I: Iterator<Item = u64>,
+{
+    nums.try_fold(0u64, |concat, num| {
+        if concat == 0 {
+            return Some(num);
+        }
+
+        let digits = num.checked_ilog10().map_or(1, |log| log + 1);
+        concat
+            .checked_mul(10u64.checked_pow(digits)?)?
+            .checked_add(num)
     })
-    .parse(input)
-    .unwrap();
+}
+
+#[aoc(day6, part2)]
+pub fn part2(races: &[Race]) -> Result<u64, OverflowError> {
+    let race = Race {
+        duration: concat_digits(races.iter().map(|race| race.duration))
+            .ok_or(OverflowError { what: "race time" })?,
+        record_distance: concat_digits(races.iter().map(|race| race.record_distance)).ok_or(
+            OverflowError {
+                what: "record distance",
+            },
+        )?,
+    };
+
+    Ok(race.count_winning_holds())
+}
+
+pub struct Day6;
 
-    result
+impl Solution for Day6 {
+    const DAY: u32 = 6;
+    const NAME: &'static str = "Wait For It";
+
+    type Input = Vec<Race>;
+    type Output1 = Result<u64, OverflowError>;
+    type Output2 = Result<u64, OverflowError>;
+    type Error = ParseError;
+
+    fn parse(input: &str) -> Result<Vec<Race>, ParseError> {
+        input_generator(input)
+    }
+
+    fn part1(races: &Vec<Race>) -> Result<u64, OverflowError> {
+        part1(races)
+    }
+
+    fn part2(races: &Vec<Race>) -> Result<u64, OverflowError> {
+        part2(races)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use indoc::indoc;
+    use crate::fixtures::{fixture, fixture_tests};
+
+    fixture_tests!(6, Day6);
 
     #[test]
     pub fn test_parse() {
-        let races = input_generator(indoc! {
-            "
-            Time:      7  15   30
-            Distance:  9  40  200
-            "
-        });
+        let races = input_generator(&fixture(6, "example")).unwrap();
 
         assert_eq!(races.len(), 3);
         assert_eq!(races[1].duration, 15);
         assert_eq!(races[1].record_distance, 40);
     }
+
+    #[test]
+    pub fn test_parse_mismatched_lists() {
+        let err = input_generator("Time: 7 15 30\nDistance: 9 40\n")
+            .err()
+            .unwrap();
+
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.snippet, "Distance: 9 40");
+        assert_eq!(err.expected, "3 distances, one for each time");
+
+        assert!(input_generator("Time: 7\nDistance: 9 40\n").is_err());
+    }
+
+    #[test]
+    pub fn test_large_numbers() {
+        let races = input_generator(&format!(
+            "Time: 300 {}\nDistance: 256 {}\n",
+            u32::MAX as u64 + 1,
+            1u64 << 40
+        ))
+        .unwrap();
+
+        assert_eq!(races[0].duration, 300);
+        assert_eq!(races[1].duration, 1 << 32);
+        assert_eq!(races[1].record_distance, 1 << 40);
+
+        let err = input_generator(&format!("Time: {}0\nDistance: 1\n", u64::MAX))
+            .err()
+            .unwrap();
+        assert_eq!((err.line, err.column), (1, 7));
+        assert_eq!(err.expected, "a number that fits in a u64");
+    }
+
+    #[test]
+    pub fn test_overflow() {
+        assert_eq!(concat_digits([7, 15, 30].into_iter()), Some(71530));
+        assert_eq!(concat_digits([0, 5, 0].into_iter()), Some(50));
+        assert_eq!(concat_digits([u64::MAX].into_iter()), Some(u64::MAX));
+        assert_eq!(concat_digits([1, u64::MAX].into_iter()), None);
+
+        let races = input_generator("Time: 99999999999 99999999999\nDistance: 1 1\n").unwrap();
+        assert_eq!(
+            part2(&races).unwrap_err().to_string(),
+            "the race time does not fit in a u64"
+        );
+
+        let races = input_generator(&format!(
+            "Time: {0} {0} {0}\nDistance: 1 1 1\n",
+            u32::MAX as u64 * 4
+        ))
+        .unwrap();
+        assert!(part1(&races).is_err());
+    }
+
+    #[test]
+    pub fn test_count_winning_holds() {
+        let race = |duration, record_distance| Race {
+            duration,
+            record_distance,
+        };
+
+        assert_eq!(race(7, 9).count_winning_holds(), 4);
+        assert_eq!(race(15, 40).count_winning_holds(), 8);
+        assert_eq!(race(30, 200).count_winning_holds(), 9);
+        // Tying the record is not a win.
+        assert_eq!(race(4, 4).count_winning_holds(), 0);
+        assert_eq!(race(4, 3).count_winning_holds(), 1);
+        assert_eq!(race(3, 100).count_winning_holds(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_serialize() {
+        let races = input_generator(&fixture(6, "example")).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&races[..1]).unwrap(),
+            serde_json::json!([{ "duration": 7, "record_distance": 9 }])
+        );
+    }
 }
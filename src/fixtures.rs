@@ -0,0 +1,194 @@
+//! Puzzle examples shared by the day tests. Each day has a directory
+//! `fixtures/2023/dayN/` holding one `<name>.txt` file per example and an
+//! `answers.json` with the expected answer to each part, keyed by name:
+//! `{ "example": { "1": "13", "2": "30" } }`. A part without an answer is not
+//! checked for that example.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use crate::{registry, solution::Solution};
+
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/2023");
+
+pub struct Fixture {
+    pub name: String,
+    pub input: String,
+    pub answers: BTreeMap<u32, String>,
+}
+
+fn day_dir(day: u32) -> PathBuf {
+    PathBuf::from(FIXTURE_DIR).join(format!("day{day}"))
+}
+
+/// The input of a single example.
+pub fn fixture(day: u32, name: &str) -> String {
+    let path = day_dir(day).join(format!("{name}.txt"));
+
+    fs::read_to_string(&path).unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()))
+}
+
+/// Every example for `day`, in name order.
+pub fn fixtures(day: u32) -> Vec<Fixture> {
+    let dir = day_dir(day);
+    let answers_path = dir.join("answers.json");
+    let json = fs::read_to_string(&answers_path)
+        .unwrap_or_else(|err| panic!("cannot read {}: {err}", answers_path.display()));
+    let mut answers: BTreeMap<String, BTreeMap<u32, String>> = serde_json::from_str(&json)
+        .unwrap_or_else(|err| panic!("cannot parse {}: {err}", answers_path.display()));
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("cannot read {}: {err}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    let fixtures: Vec<Fixture> = names
+        .into_iter()
+        .map(|name| Fixture {
+            input: fixture(day, &name),
+            answers: answers.remove(&name).unwrap_or_default(),
+            name,
+        })
+        .collect();
+
+    let orphans: Vec<_> = answers.keys().collect();
+    assert!(
+        orphans.is_empty(),
+        "{} has answers for missing examples: {orphans:?}",
+        answers_path.display()
+    );
+
+    fixtures
+}
+
+/// Checks that every example for `day` parses.
+pub fn check_parse(day: u32) {
+    let entry = registry::day(day).unwrap();
+
+    for fixture in fixtures(day) {
+        if let Err(err) = (entry.generate)(&fixture.input) {
+            panic!("day {day} example `{}` does not parse: {err}", fixture.name);
+        }
+    }
+}
+
+/// Checks `part` of `day` against every example that has an answer for it.
+pub fn check_part(day: u32, part: u32) {
+    let entry = registry::day(day).unwrap();
+    let mut checked = 0;
+
+    for fixture in fixtures(day) {
+        let Some(expected) = fixture.answers.get(&part) else {
+            continue;
+        };
+
+        match entry.run(part, &fixture.input) {
+            Ok(answer) => assert_eq!(
+                &answer, expected,
+                "day {day} part {part} on example `{}`",
+                fixture.name
+            ),
+            Err(err) => panic!(
+                "day {day} part {part} on example `{}` failed: {err}",
+                fixture.name
+            ),
+        }
+        checked += 1;
+    }
+
+    assert!(checked > 0, "day {day} has no examples for part {part}");
+}
+
+/// The same input as it might arrive from different editors and downloads.
+fn variants(input: &str) -> Vec<(&'static str, String)> {
+    let lines: Vec<&str> = input.lines().collect();
+    let mixed: String = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| format!("{line}{}", if i % 2 == 0 { "\r\n" } else { "\n" }))
+        .collect();
+
+    vec![
+        ("as written", input.to_string()),
+        ("with a byte order mark", format!("\u{feff}{input}")),
+        ("with CRLF line endings", lines.join("\r\n") + "\r\n"),
+        ("with mixed line endings", mixed),
+        ("without a final newline", lines.join("\n")),
+        ("with trailing blank lines", format!("{input}\n\r\n \n\n")),
+    ]
+}
+
+/// Checks that every variant of every example for `S` gives the recorded
+/// answers, both through the registry and when parsed directly with
+/// [`Solution::parse`].
+pub fn check_variants<S: Solution>(day: u32)
+where
+    S::Input: 'static,
+{
+    let entry = registry::day(day).unwrap();
+
+    for fixture in fixtures(day) {
+        for (variant, input) in variants(&fixture.input) {
+            let parsed = S::parse(&input).unwrap_or_else(|err| {
+                panic!(
+                    "day {day} example `{}` {variant} does not parse: {err}",
+                    fixture.name
+                )
+            });
+
+            for (&part, expected) in &fixture.answers {
+                let solve = entry.part(part).unwrap().solve;
+                let answers = [
+                    ("through the registry", entry.run(part, &input)),
+                    ("parsed directly", solve(&parsed)),
+                ];
+
+                for (path, answer) in answers {
+                    let answer = answer.unwrap_or_else(|err| {
+                        panic!(
+                            "day {day} part {part} on example `{}` {variant} {path} failed: {err}",
+                            fixture.name
+                        )
+                    });
+
+                    assert_eq!(
+                        &answer, expected,
+                        "day {day} part {part} on example `{}` {variant} {path}",
+                        fixture.name
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Generates tests that parse every example for a day and check both parts
+/// against the recorded answers, including with the example's line endings
+/// and surrounding whitespace changed.
+macro_rules! fixture_tests {
+    ($day:literal, $solution:ty) => {
+        #[test]
+        fn test_parse_fixtures() {
+            $crate::fixtures::check_parse($day);
+        }
+
+        #[test]
+        fn test_part1_fixtures() {
+            $crate::fixtures::check_part($day, 1);
+        }
+
+        #[test]
+        fn test_part2_fixtures() {
+            $crate::fixtures::check_part($day, 2);
+        }
+
+        #[test]
+        fn test_fixture_variants() {
+            $crate::fixtures::check_variants::<$solution>($day);
+        }
+    };
+}
+
+pub(crate) use fixture_tests;
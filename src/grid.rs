@@ -0,0 +1,265 @@
+use std::ops::{Index, IndexMut};
+
+use crate::parser::{grid, parse_all, ParseError};
+
+/// A position on a [`Grid`]. Coordinates are signed so that neighbours of
+/// edge cells can be represented; they are simply out of bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+
+    pub const fn offset(self, dx: i32, dy: i32) -> Self {
+        Point::new(self.x + dx, self.y + dy)
+    }
+
+    /// The points directly above, right of, below and left of this one.
+    pub fn neighbours4(self) -> [Point; 4] {
+        [
+            self.offset(0, -1),
+            self.offset(1, 0),
+            self.offset(0, 1),
+            self.offset(-1, 0),
+        ]
+    }
+
+    /// The eight surrounding points, in reading order.
+    pub fn neighbours8(self) -> [Point; 8] {
+        [
+            self.offset(-1, -1),
+            self.offset(0, -1),
+            self.offset(1, -1),
+            self.offset(-1, 0),
+            self.offset(1, 0),
+            self.offset(-1, 1),
+            self.offset(0, 1),
+            self.offset(1, 1),
+        ]
+    }
+}
+
+/// A dense, rectangular 2D grid stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its cells in row-major order. Panics if there are
+    /// not `width * height` of them.
+    pub fn from_cells(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "wrong number of grid cells");
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Builds a grid from a character map, one row per line. Trailing blank
+    /// lines are ignored; every other line must have the same length.
+    pub fn parse<F>(input: &str, cell: F) -> Result<Self, ParseError>
+    where
+        F: FnMut(char) -> T,
+    {
+        parse_all(input, grid(cell))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height
+    }
+
+    fn offset_of(&self, point: Point) -> Option<usize> {
+        self.contains(point)
+            .then(|| point.y as usize * self.width + point.x as usize)
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.offset_of(point).map(|offset| &self.cells[offset])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.offset_of(point).map(|offset| &mut self.cells[offset])
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on a zero size, which an empty grid would give it.
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let height = if x < self.width { self.height } else { 0 };
+        (0..height).map(move |y| &self.cells[y * self.width + x])
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Every point in the grid, in reading order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point::new(x as i32, y as i32)))
+    }
+
+    /// Every cell with its position, in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    /// The in-bounds orthogonal neighbours of `point`.
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point
+            .neighbours4()
+            .into_iter()
+            .filter(|&neighbour| self.contains(neighbour))
+    }
+
+    /// The in-bounds orthogonal and diagonal neighbours of `point`.
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point
+            .neighbours8()
+            .into_iter()
+            .filter(|&neighbour| self.contains(neighbour))
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        self.get(point)
+            .unwrap_or_else(|| panic!("{point:?} is outside the grid"))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("{point:?} is outside the grid"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn example() -> Grid<char> {
+        Grid::parse(
+            indoc! {
+                "
+                abc
+                def
+                "
+            },
+            |chr| chr,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = example();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Point::new(1, 1)], 'e');
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+
+        let grid = Grid::parse("12\r\n34\r\n\r\n", |chr| chr.to_digit(10).unwrap()).unwrap();
+        assert_eq!(grid.row(1), Some(&[3, 4][..]));
+
+        let empty = Grid::parse("", |chr| chr).unwrap();
+        assert_eq!((empty.width(), empty.height()), (0, 0));
+        assert_eq!(empty.rows().count(), 0);
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let err = Grid::parse("abc\nde\nfgh", |chr| chr).unwrap_err();
+
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.snippet, "de");
+        assert_eq!(err.expected, "a row of 3 characters");
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = example();
+
+        let rows: Vec<String> = grid.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, vec!["abc", "def"]);
+
+        let columns: Vec<String> = grid.columns().map(|column| column.collect()).collect();
+        assert_eq!(columns, vec!["ad", "be", "cf"]);
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.row(2), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+
+        let corner: Vec<char> = grid
+            .neighbours4(Point::new(0, 0))
+            .map(|point| grid[point])
+            .collect();
+        assert_eq!(corner, vec!['b', 'd']);
+
+        let middle: Vec<char> = grid
+            .neighbours8(Point::new(1, 0))
+            .map(|point| grid[point])
+            .collect();
+        assert_eq!(middle, vec!['a', 'c', 'd', 'e', 'f']);
+    }
+
+    #[test]
+    fn test_mutation() {
+        let mut grid = Grid::new(2, 2, 0);
+
+        grid[Point::new(1, 0)] = 5;
+        *grid.get_mut(Point::new(0, 1)).unwrap() += 2;
+
+        assert_eq!(
+            grid.iter().map(|(_, &cell)| cell).collect::<Vec<_>>(),
+            vec![0, 5, 2, 0]
+        );
+        assert_eq!(grid.get_mut(Point::new(2, 0)), None);
+    }
+}
@@ -2,6 +2,14 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod answers;
 pub mod days;
+#[cfg(test)]
+mod fixtures;
+pub mod grid;
+pub mod matcher;
+pub mod parser;
+pub mod registry;
+pub mod solution;
 
 aoc_lib! { year = 2023 }
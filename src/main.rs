@@ -1,3 +1,400 @@
-use aoc_runner_derive::aoc_main;
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
-aoc_main! { lib = aoc_23 }
+use aoc_23::{
+    answers::{input_path, manifest_path, Answers},
+    registry::{self, SolveResult, DAYS},
+};
+
+const USAGE: &str = "\
+usage: aoc-23 [<command> [options]]
+
+With no command, every day is solved, as with `aoc-23 run --all`.
+
+commands:
+  run     solve puzzles and print their answers
+  check   solve puzzles and compare them with the recorded answers; a part
+          without a recorded answer fails
+  time    solve puzzles repeatedly and report the mean time taken
+  export  print a day's parsed input as JSON (needs the `serde` feature)
+
+options:
+  --day N        only this day (required by `export`, and by `run` unless
+                 --all is given)
+  --part P       only this part
+  --all          every day in the registry
+  --input FILE   read the puzzle input from FILE (single day only)
+  --runs K       how many times `time` solves each part (default 10)";
+
+fn solve(day: u32, part: u32, input: &str) -> SolveResult {
+    registry::day(day)
+        .ok_or_else(|| format!("day {day} is not solved"))?
+        .run(part, input)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Run,
+    Check,
+    Time,
+    #[cfg(feature = "serde")]
+    Export,
+}
+
+struct Options {
+    mode: Mode,
+    day: Option<u32>,
+    part: Option<u32>,
+    all: bool,
+    input: Option<PathBuf>,
+    runs: u32,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u32, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{flag} needs a number"))
+}
+
+/// Exit status for a command line that could not be understood.
+const USAGE_ERROR: u8 = 2;
+
+fn parse_args<I>(mut args: I) -> Result<Options, String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut options = Options {
+        mode: Mode::Run,
+        day: None,
+        part: None,
+        all: false,
+        input: None,
+        runs: 10,
+    };
+
+    let mode = match args.next().as_deref() {
+        Some("run") => Mode::Run,
+        Some("check") => Mode::Check,
+        Some("time") => Mode::Time,
+        #[cfg(feature = "serde")]
+        Some("export") => Mode::Export,
+        #[cfg(not(feature = "serde"))]
+        Some("export") => return Err("`export` needs the `serde` feature".to_string()),
+        Some(command) => return Err(format!("unknown command `{command}`")),
+        None => {
+            options.all = true;
+            return Ok(options);
+        }
+    };
+    options.mode = mode;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" => options.day = Some(parse_number("--day", args.next())?),
+            "--part" => options.part = Some(parse_number("--part", args.next())?),
+            "--runs" => options.runs = parse_number("--runs", args.next())?.max(1),
+            "--all" => options.all = true,
+            "--input" => {
+                options.input = Some(args.next().ok_or("--input needs a file")?.into());
+            }
+            _ => return Err(format!("unknown option `{arg}`")),
+        }
+    }
+
+    if options.all && options.day.is_some() {
+        return Err("--all and --day cannot be combined".to_string());
+    }
+    if mode == Mode::Run && !options.all && options.day.is_none() {
+        return Err("`run` needs --day N or --all".to_string());
+    }
+    #[cfg(feature = "serde")]
+    if mode == Mode::Export && (options.day.is_none() || options.part.is_some()) {
+        return Err("`export` needs --day N and no --part".to_string());
+    }
+    if options.input.is_some() && options.day.is_none() {
+        return Err("--input needs --day".to_string());
+    }
+
+    Ok(options)
+}
+
+struct Outcome {
+    day: u32,
+    part: u32,
+    answer: Result<String, String>,
+    expected: Option<String>,
+    mean_time: Duration,
+}
+
+impl Outcome {
+    /// The status column, and whether the row counts as a failure.
+    fn status(&self, mode: Mode) -> (&'static str, bool) {
+        match (&self.answer, mode) {
+            (Err(_), _) => ("error", true),
+            (Ok(_), Mode::Check) => match &self.expected {
+                // Nothing to compare against, as when the manifest is lost.
+                None => ("missing", true),
+                Some(expected) if Some(expected) == self.answer.as_ref().ok() => ("pass", false),
+                Some(_) => ("FAIL", true),
+            },
+            (Ok(_), _) => ("ok", false),
+        }
+    }
+}
+
+fn run_part(day: u32, part: u32, input: &str, runs: u32) -> (Result<String, String>, Duration) {
+    let start = Instant::now();
+    let mut answer = Ok(String::new());
+
+    for _ in 0..runs {
+        answer = solve(day, part, input).map_err(|err| err.to_string());
+        if answer.is_err() {
+            break;
+        }
+    }
+
+    (answer, start.elapsed() / runs)
+}
+
+fn execute(options: &Options) -> Result<Vec<Outcome>, Box<dyn Error>> {
+    let answers = match options.mode {
+        Mode::Check => Answers::load(&manifest_path())?,
+        _ => Answers::default(),
+    };
+    let runs = match options.mode {
+        Mode::Time => options.runs,
+        _ => 1,
+    };
+
+    let days: Vec<u32> = options.day.map_or_else(
+        || DAYS.iter().map(|entry| entry.day).collect(),
+        |day| vec![day],
+    );
+    let mut outcomes = vec![];
+
+    for day in days {
+        let parts: Vec<u32> = options.part.map_or_else(
+            || match registry::day(day) {
+                Some(entry) => entry.parts.iter().map(|part| part.part).collect(),
+                None => vec![1, 2],
+            },
+            |part| vec![part],
+        );
+
+        let path = options.input.clone().unwrap_or_else(|| input_path(day));
+        let input = fs::read_to_string(&path);
+
+        for &part in &parts {
+            let (answer, mean_time) = match &input {
+                Ok(input) => run_part(day, part, input, runs),
+                Err(err) => (
+                    Err(format!("cannot read {}: {err}", path.display())),
+                    Duration::ZERO,
+                ),
+            };
+
+            outcomes.push(Outcome {
+                day,
+                part,
+                answer,
+                expected: answers.get(day, part).map(str::to_string),
+                mean_time,
+            });
+        }
+    }
+
+    Ok(outcomes)
+}
+
+fn print_table(rows: &[Vec<String>]) {
+    let mut widths = vec![0; rows[0].len()];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for (i, row) in rows.iter().enumerate() {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+
+        if i == 0 {
+            let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+            println!("{}", rule.join("  "));
+        }
+    }
+}
+
+fn report(options: &Options, outcomes: &[Outcome]) -> bool {
+    let mut header = vec!["Day", "Part", "Answer"];
+    if options.mode == Mode::Check {
+        header.push("Expected");
+    }
+    header.extend(["Status", "Time"]);
+
+    let mut rows = vec![header.iter().map(|cell| cell.to_string()).collect()];
+    let mut failed = false;
+
+    for outcome in outcomes {
+        let (status, is_failure) = outcome.status(options.mode);
+        failed |= is_failure;
+
+        let mut row = vec![
+            outcome.day.to_string(),
+            outcome.part.to_string(),
+            match &outcome.answer {
+                Ok(answer) => answer.clone(),
+                Err(err) => err.lines().next().unwrap_or_default().to_string(),
+            },
+        ];
+        if options.mode == Mode::Check {
+            row.push(outcome.expected.clone().unwrap_or_else(|| "-".to_string()));
+        }
+        row.extend([status.to_string(), format!("{:.2?}", outcome.mean_time)]);
+        rows.push(row);
+    }
+
+    print_table(&rows);
+
+    for outcome in outcomes {
+        if let Err(err) = &outcome.answer {
+            eprintln!("\nday {} part {}: {err}", outcome.day, outcome.part);
+        }
+    }
+
+    !failed
+}
+
+/// Prints the parsed input for the single day selected by `options`.
+#[cfg(feature = "serde")]
+fn export(options: &Options) -> Result<(), Box<dyn Error>> {
+    let day = options.day.expect("export needs a day");
+    let entry = registry::day(day).ok_or_else(|| format!("day {day} is not solved"))?;
+
+    let path = options.input.clone().unwrap_or_else(|| input_path(day));
+    let input = fs::read_to_string(&path)
+        .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+
+    println!("{}", entry.export(&input)?);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    #[cfg(feature = "serde")]
+    if options.mode == Mode::Export {
+        return match export(&options) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("error: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    match execute(&options) {
+        Ok(outcomes) if report(&options, &outcomes) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn outcome(answer: Result<&str, &str>, expected: Option<&str>) -> Outcome {
+        Outcome {
+            day: 1,
+            part: 1,
+            answer: answer.map(str::to_string).map_err(str::to_string),
+            expected: expected.map(str::to_string),
+            mean_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_no_arguments_runs_everything() {
+        let options = parse(&[]).unwrap();
+
+        assert!(options.mode == Mode::Run && options.all);
+        assert_eq!((options.day, options.part), (None, None));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse(&["time", "--day", "5", "--part", "2", "--runs", "0"]).unwrap();
+        assert!(options.mode == Mode::Time);
+        assert_eq!(
+            (options.day, options.part, options.runs),
+            (Some(5), Some(2), 1)
+        );
+
+        let options = parse(&["check", "--day", "3", "--input", "in.txt"]).unwrap();
+        assert_eq!(options.input, Some(PathBuf::from("in.txt")));
+        assert!(parse(&["check"]).unwrap().day.is_none());
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        let errors = [
+            (&["solve"][..], "unknown command `solve`"),
+            (&["run"], "`run` needs --day N or --all"),
+            (
+                &["run", "--all", "--day", "1"],
+                "--all and --day cannot be combined",
+            ),
+            (&["run", "--day", "x"], "--day needs a number"),
+            (&["run", "--day"], "--day needs a number"),
+            (&["check", "--input", "in.txt"], "--input needs --day"),
+            (&["check", "--input"], "--input needs a file"),
+            (&["check", "--verbose"], "unknown option `--verbose`"),
+        ];
+
+        for (args, expected) in errors {
+            assert_eq!(parse(args).err().as_deref(), Some(expected), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_status() {
+        let pass = outcome(Ok("42"), Some("42"));
+        assert_eq!(pass.status(Mode::Check), ("pass", false));
+        assert_eq!(pass.status(Mode::Run), ("ok", false));
+
+        let wrong = outcome(Ok("41"), Some("42"));
+        assert_eq!(wrong.status(Mode::Check), ("FAIL", true));
+        assert_eq!(wrong.status(Mode::Time), ("ok", false));
+
+        assert_eq!(
+            outcome(Ok("42"), None).status(Mode::Check),
+            ("missing", true)
+        );
+        assert_eq!(outcome(Ok("42"), None).status(Mode::Run), ("ok", false));
+        assert_eq!(outcome(Err("bad"), None).status(Mode::Run), ("error", true));
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+};
+
+/// A single occurrence of a pattern. `start` and `end` are byte offsets into
+/// the searched text.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Match<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: &'a V,
+}
+
+impl<V> Clone for Match<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for Match<'_, V> {}
+
+#[derive(Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    /// Indices of every pattern ending at this node, including the ones
+    /// reached through the failure links.
+    outputs: Vec<usize>,
+}
+
+/// An Aho-Corasick automaton over the bytes of a set of patterns.
+struct Automaton {
+    nodes: Vec<Node>,
+}
+
+impl Automaton {
+    fn new(patterns: Vec<Vec<u8>>) -> Self {
+        let mut nodes = vec![Node::default()];
+
+        for (index, bytes) in patterns.into_iter().enumerate() {
+            let mut state = 0;
+            for byte in bytes {
+                state = match nodes[state].next.get(&byte) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(byte, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].outputs.push(index);
+        }
+
+        // Breadth-first, so every failure target is finished before it is used.
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(u8, usize)> = nodes[state]
+                .next
+                .iter()
+                .map(|(&byte, &next)| (byte, next))
+                .collect();
+
+            for (byte, next) in edges {
+                let mut fallback = nodes[state].fail;
+                let fail = loop {
+                    if let Some(&target) = nodes[fallback].next.get(&byte) {
+                        break target;
+                    }
+                    if fallback == 0 {
+                        break 0;
+                    }
+                    fallback = nodes[fallback].fail;
+                };
+
+                nodes[next].fail = fail;
+                let inherited = nodes[fail].outputs.clone();
+                nodes[next].outputs.extend(inherited);
+                queue.push_back(next);
+            }
+        }
+
+        Automaton { nodes }
+    }
+
+    fn step(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&byte) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    /// Feeds `bytes` through the automaton, calling `on_match` with the end
+    /// offset and pattern index of every match. Stops early when `on_match`
+    /// returns `false`.
+    fn scan<I, F>(&self, bytes: I, mut on_match: F)
+    where
+        I: Iterator<Item = u8>,
+        F: FnMut(usize, usize) -> bool,
+    {
+        let mut state = 0;
+        for (i, byte) in bytes.enumerate() {
+            state = self.step(state, byte);
+            for &pattern in &self.nodes[state].outputs {
+                if !on_match(i + 1, pattern) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Finds occurrences of any of a dictionary of patterns, each mapped to a
+/// value. Matches may overlap, so `"twone"` contains both `"two"` and
+/// `"one"`. Searching from either end of the text runs in time linear in its
+/// length.
+pub struct PatternMatcher<V> {
+    patterns: Vec<(String, V)>,
+    max_len: usize,
+    forward: Automaton,
+    backward: Automaton,
+}
+
+impl<V> PatternMatcher<V> {
+    /// Builds a matcher from `(pattern, value)` pairs. Empty patterns are
+    /// ignored.
+    pub fn new<I, S>(dictionary: I) -> Self
+    where
+        I: IntoIterator<Item = (S, V)>,
+        S: Into<String>,
+    {
+        let patterns: Vec<(String, V)> = dictionary
+            .into_iter()
+            .map(|(pattern, value)| (pattern.into(), value))
+            .filter(|(pattern, _)| !pattern.is_empty())
+            .collect();
+
+        let forward = Automaton::new(
+            patterns
+                .iter()
+                .map(|(pattern, _)| pattern.bytes().collect())
+                .collect(),
+        );
+        let backward = Automaton::new(
+            patterns
+                .iter()
+                .map(|(pattern, _)| pattern.bytes().rev().collect())
+                .collect(),
+        );
+
+        PatternMatcher {
+            max_len: patterns
+                .iter()
+                .map(|(pattern, _)| pattern.len())
+                .max()
+                .unwrap_or(0),
+            patterns,
+            forward,
+            backward,
+        }
+    }
+
+    fn to_match(&self, pattern: usize, end: usize) -> Match<'_, V> {
+        let (text, value) = &self.patterns[pattern];
+        Match {
+            start: end - text.len(),
+            end,
+            value,
+        }
+    }
+
+    /// Every match in `text`, overlapping ones included, ordered by end
+    /// offset.
+    pub fn find_all(&self, text: &str) -> Vec<Match<'_, V>> {
+        let mut matches = vec![];
+        self.forward.scan(text.bytes(), |end, pattern| {
+            matches.push(self.to_match(pattern, end));
+            true
+        });
+        matches
+    }
+
+    /// The match that starts earliest in `text`, preferring the longest
+    /// pattern when several start at the same offset.
+    pub fn find_first(&self, text: &str) -> Option<Match<'_, V>> {
+        let mut best: Option<Match<V>> = None;
+
+        self.forward.scan(text.bytes(), |end, pattern| {
+            let found = self.to_match(pattern, end);
+            match best {
+                // Nothing that ends this late can start at or before `best`.
+                Some(best) if end > best.start + self.max_len => return false,
+                Some(best)
+                    if (best.start, Reverse(best.end)) <= (found.start, Reverse(found.end)) => {}
+                _ => best = Some(found),
+            }
+            true
+        });
+
+        best
+    }
+
+    /// The match that ends latest in `text`, preferring the longest pattern
+    /// when several end at the same offset.
+    pub fn find_last(&self, text: &str) -> Option<Match<'_, V>> {
+        let mut best: Option<Match<V>> = None;
+        let len = text.len();
+
+        self.backward.scan(text.bytes().rev(), |rev_end, pattern| {
+            let found = self.to_match(pattern, len - rev_end + self.patterns[pattern].0.len());
+            match best {
+                Some(best) if rev_end > (len - best.end) + self.max_len => return false,
+                Some(best)
+                    if (best.end, Reverse(best.start)) >= (found.end, Reverse(found.start)) => {}
+                _ => best = Some(found),
+            }
+            true
+        });
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping_matches() {
+        let matcher = PatternMatcher::new([("one", 1), ("two", 2), ("eight", 8)]);
+
+        let values: Vec<_> = matcher
+            .find_all("xtwoneightwo")
+            .iter()
+            .map(|found| (*found.value, found.start))
+            .collect();
+
+        assert_eq!(values, vec![(2, 1), (1, 3), (8, 5), (2, 9)]);
+        assert_eq!(
+            matcher.find_first("xtwoneightwo").map(|m| *m.value),
+            Some(2)
+        );
+        assert_eq!(matcher.find_last("xtwoneightwo").map(|m| *m.value), Some(2));
+        assert_eq!(matcher.find_last("twoneigh").map(|m| *m.value), Some(1));
+        assert_eq!(matcher.find_first("nothing here"), None);
+    }
+
+    #[test]
+    fn test_nested_patterns() {
+        let matcher = PatternMatcher::new([("bc", "inner"), ("abcd", "outer"), ("cd", "tail")]);
+
+        // "bc" finishes first, but "abcd" starts earlier.
+        let first = matcher.find_first("xabcdx").unwrap();
+        assert_eq!((first.start, first.end, *first.value), (1, 5, "outer"));
+
+        let last = matcher.find_last("xabcdx").unwrap();
+        assert_eq!((last.start, last.end, *last.value), (1, 5, "outer"));
+
+        let last = matcher.find_last("xbcdx").unwrap();
+        assert_eq!((last.start, last.end, *last.value), (2, 4, "tail"));
+    }
+
+    #[test]
+    fn test_multibyte_patterns() {
+        let matcher = PatternMatcher::new([("eins", 1), ("zwei", 2), ("drei", 3), ("fünf", 5)]);
+
+        assert_eq!(matcher.find_first("xxfünfzweiy").map(|m| *m.value), Some(5));
+        assert_eq!(matcher.find_last("xxfünfzweiy").map(|m| *m.value), Some(2));
+        assert_eq!(matcher.find_first("zweins").map(|m| *m.value), Some(2));
+        assert_eq!(matcher.find_last("zweins").map(|m| *m.value), Some(1));
+    }
+}
@@ -0,0 +1,448 @@
+use std::{any::type_name, borrow::Cow, error::Error, fmt, str::FromStr};
+
+use nom::{
+    character::complete::{digit1, line_ending, multispace0, not_line_ending, space1},
+    error::{ContextError, ErrorKind, FromExternalError},
+    multi::{many1, separated_list1},
+    sequence::{pair, preceded},
+    Parser,
+};
+
+use crate::grid::Grid;
+
+/// The result type used by every puzzle parser in this crate.
+pub type IResult<'a, O> = nom::IResult<&'a str, O, SyntaxError<'a>>;
+
+/// The nom error type used while parsing. It remembers where parsing failed
+/// and a short description of what was expected there.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError<'a> {
+    pub input: &'a str,
+    pub expected: Cow<'static, str>,
+    has_context: bool,
+}
+
+impl<'a> SyntaxError<'a> {
+    pub fn new(input: &'a str, expected: impl Into<Cow<'static, str>>) -> Self {
+        SyntaxError {
+            input,
+            expected: expected.into(),
+            has_context: false,
+        }
+    }
+}
+
+fn describe_error_kind(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Digit => "a number",
+        ErrorKind::Space | ErrorKind::MultiSpace => "whitespace",
+        ErrorKind::CrLf => "a line ending",
+        ErrorKind::Eof => "end of input",
+        ErrorKind::OneOf | ErrorKind::NoneOf | ErrorKind::Char => "a character",
+        ErrorKind::MapRes | ErrorKind::Verify => "a valid value",
+        _ => "valid input",
+    }
+}
+
+impl<'a> nom::error::ParseError<&'a str> for SyntaxError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        SyntaxError::new(input, describe_error_kind(kind))
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, chr: char) -> Self {
+        SyntaxError::new(input, format!("`{chr}`"))
+    }
+}
+
+impl<'a> ContextError<&'a str> for SyntaxError<'a> {
+    fn add_context(_input: &'a str, ctx: &'static str, mut other: Self) -> Self {
+        // Keep the innermost context, since it is closest to the failure.
+        if !other.has_context {
+            other.expected = Cow::Borrowed(ctx);
+            other.has_context = true;
+        }
+        other
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for SyntaxError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: E) -> Self {
+        SyntaxError::new(input, describe_error_kind(kind))
+    }
+}
+
+/// Matches a literal string, reporting the string itself when it is missing.
+pub fn tag<'a>(expected: &'static str) -> impl FnMut(&'a str) -> IResult<'a, &'a str> {
+    move |input: &'a str| {
+        nom::bytes::complete::tag(expected)(input).map_err(|err| {
+            err.map(|err: SyntaxError| SyntaxError::new(err.input, format!("`{expected}`")))
+        })
+    }
+}
+
+/// Cleans up raw puzzle input before it is parsed. A UTF-8 byte order mark is
+/// dropped, `\r\n` and lone `\r` line endings become `\n`, and blank lines
+/// at the end are removed. Input that is already clean is borrowed unchanged.
+pub fn normalise(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let input: Cow<str> = if input.contains('\r') {
+        input.replace("\r\n", "\n").replace('\r', "\n").into()
+    } else {
+        input.into()
+    };
+
+    // Keep the line ending after the last non-blank line, if there is one.
+    let end = match input.trim_end().len() {
+        0 => 0,
+        content_end => input[content_end..]
+            .find('\n')
+            .map_or(input.len(), |idx| content_end + idx + 1),
+    };
+
+    match input {
+        Cow::Borrowed(input) => Cow::Borrowed(&input[..end]),
+        Cow::Owned(mut input) => {
+            input.truncate(end);
+            Cow::Owned(input)
+        }
+    }
+}
+
+/// Parses an unsigned decimal number as a `T`. A number too large for `T`
+/// fails outright, naming the type, instead of letting the surrounding parser
+/// try something else.
+pub fn number<'a, T: FromStr>(input: &'a str) -> IResult<'a, T> {
+    let (rest, digits) = digit1(input)?;
+
+    match digits.parse() {
+        Ok(num) => Ok((rest, num)),
+        Err(_) => Err(nom::Err::Failure(SyntaxError::new(
+            input,
+            format!("a number that fits in a {}", type_name::<T>()),
+        ))),
+    }
+}
+
+/// Parses one or more numbers separated by spaces or tabs, such as
+/// `7  15   30`.
+pub fn numbers<'a, T: FromStr>(input: &'a str) -> IResult<'a, Vec<T>> {
+    separated_list1(space1, number)(input)
+}
+
+/// Parses `label` and the whitespace after it, then `value`.
+pub fn labelled<'a, O, P>(label: &'static str, value: P) -> impl FnMut(&'a str) -> IResult<'a, O>
+where
+    P: Parser<&'a str, O, SyntaxError<'a>>,
+{
+    preceded(pair(tag(label), space1), value)
+}
+
+/// Parses a labelled list of numbers such as `Time:      7  15   30`.
+pub fn labelled_numbers<'a, T: FromStr>(
+    label: &'static str,
+) -> impl FnMut(&'a str) -> IResult<'a, Vec<T>> {
+    labelled(label, numbers)
+}
+
+/// Matches the end of a line and at least one blank line after it.
+pub fn blank_lines(input: &str) -> IResult<'_, ()> {
+    pair(line_ending, many1(line_ending))
+        .map(|_| ())
+        .parse(input)
+}
+
+/// Parses one or more `element`s separated by blank lines.
+pub fn blocks<'a, O, P>(element: P) -> impl FnMut(&'a str) -> IResult<'a, Vec<O>>
+where
+    P: Parser<&'a str, O, SyntaxError<'a>>,
+{
+    separated_list1(blank_lines, element)
+}
+
+/// Parses a rectangular character map, one row per line, up to the first
+/// blank line. Every row must be as long as the first.
+pub fn grid<'a, T, F>(mut cell: F) -> impl FnMut(&'a str) -> IResult<'a, Grid<T>>
+where
+    F: FnMut(char) -> T,
+{
+    move |input: &'a str| {
+        let mut cells = vec![];
+        let mut width = None;
+        let mut height = 0;
+        // `end` stays at the end of the last row, so that the line ending
+        // after the grid is left for whatever follows it.
+        let mut end = input;
+        let mut next_row = input;
+
+        loop {
+            let (rest, row) = not_line_ending::<_, SyntaxError>(next_row)?;
+            if row.is_empty() {
+                break;
+            }
+
+            let len = row.chars().count();
+            let width = *width.get_or_insert(len);
+            if len != width {
+                let column = row
+                    .char_indices()
+                    .nth(width)
+                    .map_or(row.len(), |(idx, _)| idx);
+                return Err(nom::Err::Failure(SyntaxError::new(
+                    &next_row[column..],
+                    format!("a row of {width} characters"),
+                )));
+            }
+
+            cells.extend(row.chars().map(&mut cell));
+            height += 1;
+            end = rest;
+
+            match line_ending::<_, SyntaxError>(rest) {
+                Ok((rest, _)) => next_row = rest,
+                Err(_) => break,
+            }
+        }
+
+        Ok((end, Grid::from_cells(width.unwrap_or(0), height, cells)))
+    }
+}
+
+/// Parses one `element` per line until only whitespace remains. Unlike
+/// `separated_list0(line_ending, element)`, a line that fails to parse is
+/// reported as an error rather than silently ending the list.
+pub fn lines<'a, O, P>(mut element: P) -> impl FnMut(&'a str) -> IResult<'a, Vec<O>>
+where
+    P: Parser<&'a str, O, SyntaxError<'a>>,
+{
+    move |mut input: &'a str| {
+        let mut items = vec![];
+
+        while !input.trim().is_empty() {
+            let (rest, item) = element.parse(input)?;
+            items.push(item);
+
+            match line_ending::<_, SyntaxError>(rest) {
+                Ok((rest, _)) => input = rest,
+                Err(_) => return Ok((rest, items)),
+            }
+        }
+
+        Ok((input, items))
+    }
+}
+
+/// A parse failure, located by its 1-based line and column in the puzzle
+/// input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    /// Locates `remaining`, which must be a suffix of `source`.
+    pub fn at(source: &str, remaining: &str, expected: impl Into<String>) -> Self {
+        let offset = source.len() - remaining.len();
+        let before = &source[..offset];
+
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |idx| offset + idx);
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: source[line_start..offset].chars().count() + 1,
+            snippet: source[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            expected: expected.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {} at line {} col {}\n  {}\n  {:>width$}",
+            self.expected,
+            self.line,
+            self.column,
+            self.snippet,
+            "^",
+            width = self.column
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Runs `parser` over the whole of `input`. Anything other than trailing
+/// whitespace left over once the parser finishes is reported as an error.
+pub fn parse_all<'a, O, P>(input: &'a str, mut parser: P) -> Result<O, ParseError>
+where
+    P: Parser<&'a str, O, SyntaxError<'a>>,
+{
+    let (rest, output) = parser.parse(input).map_err(|err| match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => {
+            ParseError::at(input, err.input, err.expected)
+        }
+        nom::Err::Incomplete(_) => ParseError::at(input, "", "more input"),
+    })?;
+
+    let (rest, _) = multispace0::<_, SyntaxError>(rest).expect("multispace0 cannot fail");
+
+    if !rest.is_empty() {
+        return Err(ParseError::at(input, rest, "end of input"));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::{
+        character::complete::{char, u32},
+        multi::separated_list1,
+        sequence::separated_pair,
+    };
+
+    #[test]
+    fn test_parse_all() {
+        let nums = parse_all("1\n2\n3\n", separated_list1(line_ending, u32));
+
+        assert_eq!(nums, Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_error_location() {
+        let err = parse_all(
+            "Num: 1\nNum: 2\nNum x\n",
+            separated_list1(line_ending, tag("Num: ").and(u32)),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 1);
+        assert_eq!(err.snippet, "Num x");
+        assert_eq!(err.expected, "end of input");
+
+        let err = parse_all("Num: 1\nNum: x", tag("Num: ").and(u32)).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "expected end of input at line 2 col 1\n  Num: x\n  ^"
+        );
+
+        let err = parse_all("Num: x", tag("Num: ").and(u32)).unwrap_err();
+
+        assert_eq!((err.line, err.column), (1, 6));
+        assert_eq!(err.expected, "a number");
+    }
+
+    #[test]
+    fn test_normalise() {
+        assert_eq!(normalise("a\nb\n"), "a\nb\n");
+        assert!(matches!(normalise("a\nb\n\n"), Cow::Borrowed("a\nb\n")));
+        assert_eq!(normalise("\u{feff}a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalise("a  \r\n \r\n\t\n\n"), "a  \n");
+        assert_eq!(normalise("\u{feff}\r\n \n"), "");
+        assert_eq!(normalise("\n\na\n"), "\n\na\n");
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(parse_all("300", number::<u16>), Ok(300));
+        assert_eq!(
+            parse_all("4294967296", number::<u64>),
+            Ok(u32::MAX as u64 + 1)
+        );
+
+        let err = parse_all("1 70000", separated_list1(char(' '), number::<u16>)).unwrap_err();
+        assert_eq!((err.line, err.column), (1, 3));
+        assert_eq!(err.expected, "a number that fits in a u16");
+
+        let err = parse_all("x", number::<u8>).unwrap_err();
+        assert_eq!(err.expected, "a number");
+    }
+
+    #[test]
+    fn test_labelled_numbers() {
+        let parsed = parse_all(
+            "Time:      7  15\t30\r\nDistance: 9\r\n",
+            separated_pair(
+                labelled_numbers::<u8>("Time:"),
+                line_ending,
+                labelled_numbers::<u64>("Distance:"),
+            ),
+        );
+        assert_eq!(parsed, Ok((vec![7, 15, 30], vec![9])));
+
+        let err = parse_all("Time:7", labelled_numbers::<u8>("Time:")).unwrap_err();
+        assert_eq!((err.column, err.expected.as_str()), (6, "whitespace"));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let expected = vec![vec![1, 2], vec![3], vec![4, 5]];
+
+        for input in ["1 2\n\n3\n\n4 5", "1 2\r\n\r\n3\r\n\r\n\r\n4 5\r\n\r\n"] {
+            assert_eq!(
+                parse_all(input, blocks(numbers::<u32>)),
+                Ok(expected.clone())
+            );
+        }
+
+        let err = parse_all("1 2\n3", blocks(numbers::<u32>)).unwrap_err();
+        assert_eq!((err.line, err.expected.as_str()), (2, "end of input"));
+    }
+
+    #[test]
+    fn test_grid() {
+        let (first, second) = parse_all(
+            "ab\r\ncd\r\n\r\nxyz\n",
+            separated_pair(grid(|chr| chr), blank_lines, grid(|chr| chr)),
+        )
+        .unwrap();
+
+        assert_eq!((first.width(), first.height()), (2, 2));
+        assert_eq!(first.row(1), Some(&['c', 'd'][..]));
+        assert_eq!((second.width(), second.height()), (3, 1));
+
+        let err = parse_all("ab\nabc\n", grid(|chr| chr)).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.expected, "a row of 2 characters");
+    }
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(parse_all("1\n2\n\n", lines(u32)), Ok(vec![1, 2]));
+        assert_eq!(parse_all("", lines(u32)), Ok(vec![]));
+
+        let err = parse_all("1\n2\nx\n4", lines(u32)).unwrap_err();
+
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.expected, "a number");
+    }
+
+    #[test]
+    fn test_error_expectations() {
+        let err = parse_all("Num 1", tag("Num: ").and(u32)).unwrap_err();
+        assert_eq!(err.expected, "`Num: `");
+
+        let err = parse_all("ab", char('a').and(char(':'))).unwrap_err();
+        assert_eq!((err.column, err.expected.as_str()), (2, "`:`"));
+
+        let err =
+            parse_all("x", nom::error::context("`y|z`", char('y').or(char('z')))).unwrap_err();
+        assert_eq!(err.expected, "`y|z`");
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    any::{type_name, Any},
+    error::Error,
+    fmt::Display,
+};
+
+use crate::{
+    days::{day1, day2, day3, day4, day5, day6},
+    solution::Solution,
+};
+
+pub type SolveResult = Result<String, Box<dyn Error>>;
+
+/// A day's parsed puzzle input, as produced by [`Day::generate`].
+pub type Parsed = Box<dyn Any>;
+
+/// Converts whatever a solver returns into its printed answer.
+trait Answer {
+    fn into_answer(self) -> SolveResult;
+}
+
+macro_rules! impl_answer {
+    ($($ty:ty),*) => {
+        $(impl Answer for $ty {
+            fn into_answer(self) -> SolveResult {
+                Ok(self.to_string())
+            }
+        })*
+    };
+}
+
+impl_answer!(u32, u64, u128);
+
+impl<T: Display, E: Into<Box<dyn Error>>> Answer for Result<T, E> {
+    fn into_answer(self) -> SolveResult {
+        self.map(|answer| answer.to_string()).map_err(Into::into)
+    }
+}
+
+pub struct Part {
+    pub part: u32,
+    /// The name of the Rust type the solver returns.
+    pub output: fn() -> &'static str,
+    /// Solves this part for input produced by the day's generator.
+    pub solve: fn(&dyn Any) -> SolveResult,
+}
+
+pub struct Day {
+    pub day: u32,
+    pub name: &'static str,
+    /// The name of the Rust type the generator produces.
+    pub input: fn() -> &'static str,
+    /// Parses the raw puzzle input.
+    pub generate: fn(&str) -> Result<Parsed, Box<dyn Error>>,
+    /// Renders input produced by `generate` as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    pub export: fn(&dyn Any) -> Result<String, serde_json::Error>,
+    pub parts: &'static [Part],
+}
+
+impl Day {
+    pub fn part(&self, part: u32) -> Option<&Part> {
+        self.parts.iter().find(|entry| entry.part == part)
+    }
+
+    /// Parses `input` and solves `part` on it.
+    pub fn run(&self, part: u32, input: &str) -> SolveResult {
+        let solver = self
+            .part(part)
+            .ok_or_else(|| format!("day {} part {part} is not solved", self.day))?;
+
+        (solver.solve)((self.generate)(input)?.as_ref())
+    }
+
+    /// Parses `input` and renders the result as JSON.
+    #[cfg(feature = "serde")]
+    pub fn export(&self, input: &str) -> Result<String, Box<dyn Error>> {
+        Ok((self.export)((self.generate)(input)?.as_ref())?)
+    }
+}
+
+/// The parsed input of `S`, from a value produced by its generator.
+fn downcast<S: Solution>(parsed: &dyn Any) -> &S::Input
+where
+    S::Input: 'static,
+{
+    parsed.downcast_ref().unwrap_or_else(|| {
+        panic!(
+            "{} input was not a {}",
+            type_name::<S>(),
+            type_name::<S::Input>()
+        )
+    })
+}
+
+macro_rules! part {
+    ($part:literal, $solution:ty, $solver:ident, $output:ident) => {
+        Part {
+            part: $part,
+            output: type_name::<<$solution as Solution>::$output>,
+            solve: |parsed| <$solution>::$solver(downcast::<$solution>(parsed)).into_answer(),
+        }
+    };
+}
+
+/// Builds the entry for a [`Solution`], taking the input and output types
+/// from the implementation.
+macro_rules! day {
+    ($solution:ty) => {
+        Day {
+            day: <$solution>::DAY,
+            name: <$solution>::NAME,
+            input: type_name::<<$solution as Solution>::Input>,
+            generate: |input| Ok(Box::new(<$solution>::parse(input)?)),
+            #[cfg(feature = "serde")]
+            export: |parsed| serde_json::to_string_pretty(downcast::<$solution>(parsed)),
+            parts: &[
+                part!(1, $solution, part1, Output1),
+                part!(2, $solution, part2, Output2),
+            ],
+        }
+    };
+}
+
+/// Every solved day, in order.
+pub static DAYS: &[Day] = &[
+    day!(day1::Day1),
+    day!(day2::Day2),
+    day!(day3::Day3),
+    day!(day4::Day4),
+    day!(day5::Day5),
+    day!(day6::Day6),
+];
+
+pub fn day(day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|entry| entry.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_days() {
+        let days: Vec<u32> = DAYS.iter().map(|entry| entry.day).collect();
+
+        assert_eq!(days, vec![1, 2, 3, 4, 5, 6]);
+        assert!(DAYS.iter().all(|entry| entry.parts.len() == 2));
+        assert_eq!((day(3).unwrap().input)(), "aoc_23::days::day3::Board");
+        assert_eq!((day(3).unwrap().part(2).unwrap().output)(), "u64");
+        assert!((day(6).unwrap().part(1).unwrap().output)().contains("OverflowError"));
+        assert!(day(7).is_none());
+    }
+
+    #[test]
+    fn test_run() {
+        let input = indoc! {
+            "
+            Time:      7  15   30
+            Distance:  9  40  200
+            "
+        };
+        let day6 = day(6).unwrap();
+
+        assert_eq!(day6.run(1, input).unwrap(), "288");
+        assert_eq!(day6.run(2, input).unwrap(), "71503");
+        assert!(day6.run(3, input).is_err());
+        assert!(day6.run(1, "Time: x").is_err());
+
+        let parsed = (day6.generate)(input).unwrap();
+        assert_eq!(
+            (day6.part(1).unwrap().solve)(parsed.as_ref()).unwrap(),
+            "288"
+        );
+    }
+
+    #[test]
+    fn test_solver_errors() {
+        let error = day(1).unwrap().run(1, "abc\n").unwrap_err();
+
+        assert!(error.to_string().contains("line 1 contains no digits"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_export() {
+        let exported = day(1).unwrap().export("1abc2\r\n").unwrap();
+        assert_eq!(exported, "\"1abc2\\n\"");
+
+        for entry in DAYS {
+            for fixture in crate::fixtures::fixtures(entry.day) {
+                let json = entry.export(&fixture.input).unwrap();
+                assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+            }
+        }
+
+        assert!(day(6).unwrap().export("Time: x").is_err());
+    }
+}
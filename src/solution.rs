@@ -0,0 +1,53 @@
+use std::error::Error;
+
+/// A day's puzzle: how to parse its input and how to solve each part. Every
+/// module under [`days`](crate::days) has one implementation, named after
+/// the day, such as [`Day2`](crate::days::day2::Day2).
+pub trait Solution {
+    /// The day of December the puzzle was released on.
+    const DAY: u32;
+    /// The puzzle's title.
+    const NAME: &'static str;
+
+    /// The parsed puzzle input that both parts are solved from.
+    type Input;
+    type Output1;
+    type Output2;
+    /// Why puzzle input could not be parsed.
+    type Error: Error + 'static;
+
+    /// Parses puzzle input, accepting any line endings, a byte order mark
+    /// and trailing blank lines.
+    fn parse(input: &str) -> Result<Self::Input, Self::Error>;
+
+    fn part1(input: &Self::Input) -> Self::Output1;
+
+    fn part2(input: &Self::Input) -> Self::Output2;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        days::{day1::Day1, day2::Day2, day6::Day6},
+        fixtures::fixture,
+    };
+
+    fn solve<S: Solution>(input: &str) -> Result<(S::Output1, S::Output2), S::Error> {
+        let parsed = S::parse(input)?;
+
+        Ok((S::part1(&parsed), S::part2(&parsed)))
+    }
+
+    #[test]
+    fn test_generic_solve() {
+        assert_eq!(
+            solve::<Day6>(&fixture(6, "example")).unwrap(),
+            (Ok(288), Ok(71503))
+        );
+        assert_eq!(solve::<Day2>(&fixture(2, "example")).unwrap(), (8, 2286));
+        assert_eq!(solve::<Day1>(&fixture(1, "example2")).unwrap().1, Ok(281));
+        assert!(solve::<Day6>("Time: 7").is_err());
+        assert_eq!((Day2::DAY, Day2::NAME), (2, "Cube Conundrum"));
+    }
+}
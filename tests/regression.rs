@@ -0,0 +1,83 @@
+//! Runs every registered solver on its real input and compares the result
+//! with `input/2023/answers.json`.
+//!
+//! Set `ACCEPT_ANSWERS=1` to record the current answer for any part that does
+//! not have one yet, or `ACCEPT_ANSWERS=all` to overwrite every recorded
+//! answer with the current one. Leaving it unset, empty or `0` only checks
+//! the answers; any other value fails the test.
+
+use std::{env, fs};
+
+use aoc_23::{
+    answers::{input_path, manifest_path, Answers},
+    registry::DAYS,
+};
+
+#[derive(PartialEq)]
+enum Accept {
+    None,
+    Missing,
+    All,
+}
+
+#[test]
+fn test_recorded_answers() {
+    let accept = match env::var("ACCEPT_ANSWERS").as_deref() {
+        Ok("all") => Accept::All,
+        Ok("1") => Accept::Missing,
+        Ok("" | "0") | Err(env::VarError::NotPresent) => Accept::None,
+        Ok(other) => panic!("ACCEPT_ANSWERS must be `1`, `all` or `0`, not `{other}`"),
+        Err(err) => panic!("cannot read ACCEPT_ANSWERS: {err}"),
+    };
+
+    let mut answers = Answers::load(&manifest_path()).unwrap();
+    let mut accepted = false;
+    let mut failures = vec![];
+
+    for day in DAYS {
+        let input = match fs::read_to_string(input_path(day.day)) {
+            Ok(input) => input,
+            Err(err) => {
+                failures.push(format!("day {}: cannot read input: {err}", day.day));
+                continue;
+            }
+        };
+
+        for part in day.parts {
+            let answer = match day.run(part.part, &input) {
+                Ok(answer) => answer,
+                Err(err) => {
+                    failures.push(format!("day {} part {}: {err}", day.day, part.part));
+                    continue;
+                }
+            };
+
+            match answers.get(day.day, part.part) {
+                Some(expected) if expected == answer => {}
+                Some(_) | None if accept == Accept::All => {
+                    answers.set(day.day, part.part, answer);
+                    accepted = true;
+                }
+                None if accept == Accept::Missing => {
+                    answers.set(day.day, part.part, answer);
+                    accepted = true;
+                }
+                Some(expected) => failures.push(format!(
+                    "day {} part {}: expected {expected}, got {answer}",
+                    day.day, part.part
+                )),
+                None => failures.push(format!(
+                    "day {} part {}: no recorded answer (got {answer}); \
+                     rerun with ACCEPT_ANSWERS=1 to record it",
+                    day.day, part.part
+                )),
+            }
+        }
+    }
+
+    if accepted {
+        answers.save(&manifest_path()).unwrap();
+    }
+
+    assert!(failures.is_empty(), "\n{}\n", failures.join("\n"));
+}